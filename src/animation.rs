@@ -0,0 +1,333 @@
+//! Keyframed animation of objects and cameras
+
+use std::sync::Arc;
+
+use crate::{
+    camera::CameraBuilder,
+    hit::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+/// Values that can be interpolated between keyframes
+pub trait Keyable: Copy {
+    /// Sum of two values
+    fn add(self, other: Self) -> Self;
+    /// Value multiplied by `factor`
+    fn scale(self, factor: f64) -> Self;
+}
+
+impl Keyable for f64 {
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        self * factor
+    }
+}
+
+impl Keyable for Vec3 {
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        self.mul(factor)
+    }
+}
+
+/// How a track moves between its keyframes
+#[derive(Clone, Copy)]
+pub enum Interpolation {
+    /// Straight from one key to the next
+    Linear,
+    /// Cubic Hermite spline through the keys with Catmull-Rom tangents, which eases in and out
+    /// of changes in direction
+    Spline,
+}
+
+/// Value changing over time, given at keyframes in seconds and held before the first and after
+/// the last
+#[derive(Clone)]
+pub struct Track<T: Keyable> {
+    keys: Vec<(f64, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T: Keyable> Track<T> {
+    /// Track with its first key, `value` at `time` seconds
+    pub fn new(interpolation: Interpolation, time: f64, value: T) -> Self {
+        Self {
+            keys: vec![(time, value)],
+            interpolation,
+        }
+    }
+
+    /// Track holding `value` throughout
+    pub fn constant(value: T) -> Self {
+        Self::new(Interpolation::Linear, 0.0, value)
+    }
+
+    /// Track with `value` keyed at `time` seconds
+    pub fn with_key(mut self, time: f64, value: T) -> Self {
+        let index = self.keys.partition_point(|(key, _)| *key <= time);
+        self.keys.insert(index, (time, value));
+        self
+    }
+
+    /// Value at `time` seconds
+    pub fn value(&self, time: f64) -> T {
+        let last = self.keys.len() - 1;
+        let next = self.keys.partition_point(|(key, _)| *key <= time);
+        if next == 0 {
+            return self.keys[0].1;
+        }
+        if next > last {
+            return self.keys[last].1;
+        }
+
+        let (i, j) = (next - 1, next);
+        let ((t0, p0), (t1, p1)) = (self.keys[i], self.keys[j]);
+        let duration = t1 - t0;
+        let s = (time - t0) / duration;
+
+        match self.interpolation {
+            Interpolation::Linear => p0.scale(1.0 - s).add(p1.scale(s)),
+            Interpolation::Spline => {
+                let m0 = self.tangent(i).scale(duration);
+                let m1 = self.tangent(j).scale(duration);
+                let (s2, s3) = (s * s, s * s * s);
+                p0.scale(2.0 * s3 - 3.0 * s2 + 1.0)
+                    .add(m0.scale(s3 - 2.0 * s2 + s))
+                    .add(p1.scale(-2.0 * s3 + 3.0 * s2))
+                    .add(m1.scale(s3 - s2))
+            }
+        }
+    }
+
+    // Rate of change at key `i`, from its neighbours or a one sided difference at the ends
+    fn tangent(&self, i: usize) -> T {
+        let before = i.saturating_sub(1);
+        let after = (i + 1).min(self.keys.len() - 1);
+        let ((t0, p0), (t1, p1)) = (self.keys[before], self.keys[after]);
+        if t1 <= t0 {
+            return p0.scale(0.0);
+        }
+
+        p1.add(p0.scale(-1.0)).scale(1.0 / (t1 - t0))
+    }
+}
+
+/// Animated placement of an object: scaled uniformly about its origin, rotated about the x, y
+/// and z axes in turn by angles in degrees, then translated
+#[derive(Clone)]
+pub struct Transform {
+    translation: Track<Vec3>,
+    rotation: Track<Vec3>,
+    scale: Track<f64>,
+}
+
+impl Transform {
+    /// Transform leaving the object where it is
+    pub fn new() -> Self {
+        Self {
+            translation: Track::constant(Vec3::new()),
+            rotation: Track::constant(Vec3::new()),
+            scale: Track::constant(1.0),
+        }
+    }
+
+    /// Translation in world units over time
+    pub fn with_translation(self, translation: Track<Vec3>) -> Self {
+        Self {
+            translation,
+            ..self
+        }
+    }
+
+    /// Rotation about each axis in degrees over time
+    pub fn with_rotation(self, rotation: Track<Vec3>) -> Self {
+        Self { rotation, ..self }
+    }
+
+    /// Scale over time
+    pub fn with_scale(self, scale: Track<f64>) -> Self {
+        Self { scale, ..self }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Transform evaluated at one point in time
+#[derive(Clone, Copy)]
+struct Pose {
+    translation: Vec3,
+    // Sine and cosine of the rotation about each axis
+    rotation: [(f64, f64); 3],
+    scale: f64,
+}
+
+impl Pose {
+    fn new(transform: &Transform, time: f64) -> Self {
+        let angles = transform.rotation.value(time);
+        Self {
+            translation: transform.translation.value(time),
+            rotation: [0, 1, 2].map(|axis| angles[axis].to_radians().sin_cos()),
+            scale: transform.scale.value(time),
+        }
+    }
+
+    fn rotate(&self, v: Vec3) -> Vec3 {
+        (0..3).fold(v, |v, axis| rotate_axis(v, axis, self.rotation[axis]))
+    }
+
+    fn unrotate(&self, v: Vec3) -> Vec3 {
+        (0..3).rev().fold(v, |v, axis| {
+            let (sin, cos) = self.rotation[axis];
+            rotate_axis(v, axis, (-sin, cos))
+        })
+    }
+
+    fn object_point(&self, p: Vec3) -> Vec3 {
+        self.unrotate(p - self.translation).div(self.scale)
+    }
+
+    fn world_point(&self, p: Vec3) -> Vec3 {
+        self.rotate(p.mul(self.scale)) + self.translation
+    }
+}
+
+// Rotates `v` about the x, y or z axis given the angle's sine and cosine
+fn rotate_axis(v: Vec3, axis: usize, (sin, cos): (f64, f64)) -> Vec3 {
+    let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut rotated = v;
+    rotated[a] = cos * v[a] - sin * v[b];
+    rotated[b] = sin * v[a] + cos * v[b];
+    rotated
+}
+
+/// Object moved by a keyframed transform, posed for each frame by `animate`
+#[derive(Clone)]
+pub struct Animated<T: Hittable> {
+    object: T,
+    transform: Transform,
+    pose: Pose,
+}
+
+impl<T: Hittable> Animated<T> {
+    /// Animates `object` by `transform`, posed at time zero until animated
+    pub fn new(object: T, transform: Transform) -> Self {
+        let pose = Pose::new(&transform, 0.0);
+        Self {
+            object,
+            transform,
+            pose,
+        }
+    }
+}
+
+impl<T: Hittable> Hittable for Animated<T> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        // Hit the object in its own space, where the ray's parameter is unchanged since the
+        // direction is scaled along with the origin
+        let pose = &self.pose;
+        let origin = pose.object_point(*ray.origin());
+        let direction = pose.unrotate(*ray.direction()).div(pose.scale);
+        let local = Ray::from(origin, direction).with_wavelength(ray.wavelength());
+        let mut record = self.object.hit(&local, ray_t)?;
+
+        record.p = pose.world_point(record.p);
+        record.normal = pose.rotate(record.normal);
+        record.tangent = pose.rotate(record.tangent.mul(pose.scale));
+        record.bitangent = pose.rotate(record.bitangent.mul(pose.scale));
+
+        Some(record)
+    }
+
+    // Rotations and uniform scaling preserve solid angles, so densities carry over as they are
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let origin = self.pose.object_point(*origin);
+        self.object
+            .pdf_value(&origin, &self.pose.unrotate(*direction))
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let origin = self.pose.object_point(*origin);
+        self.pose.rotate(self.object.random(&origin))
+    }
+
+    fn animate(&mut self, time: f64) {
+        self.object.animate(time);
+        self.pose = Pose::new(&self.transform, time);
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        self.object.materials(materials);
+    }
+}
+
+/// Keyframed camera parameters, overriding the builder's own when set
+#[derive(Clone, Default)]
+pub struct CameraAnimation {
+    look_from: Option<Track<Vec3>>,
+    look_at: Option<Track<Vec3>>,
+    v_fov: Option<Track<f64>>,
+    focus_dist: Option<Track<f64>>,
+}
+
+impl CameraAnimation {
+    /// Keyframes for `CameraBuilder::look_from`
+    pub fn with_look_from(self, look_from: Track<Vec3>) -> Self {
+        Self {
+            look_from: Some(look_from),
+            ..self
+        }
+    }
+
+    /// Keyframes for `CameraBuilder::look_at`
+    pub fn with_look_at(self, look_at: Track<Vec3>) -> Self {
+        Self {
+            look_at: Some(look_at),
+            ..self
+        }
+    }
+
+    /// Keyframes for `CameraBuilder::v_fov`, in degrees
+    pub fn with_v_fov(self, v_fov: Track<f64>) -> Self {
+        Self {
+            v_fov: Some(v_fov),
+            ..self
+        }
+    }
+
+    /// Keyframes for `CameraBuilder::focus_dist`
+    pub fn with_focus_dist(self, focus_dist: Track<f64>) -> Self {
+        Self {
+            focus_dist: Some(focus_dist),
+            ..self
+        }
+    }
+
+    /// Sets the animated parameters of `camera` to their values at `time`
+    pub fn apply(&self, camera: &mut CameraBuilder, time: f64) {
+        if let Some(track) = &self.look_from {
+            camera.look_from(track.value(time));
+        }
+        if let Some(track) = &self.look_at {
+            camera.look_at(track.value(time));
+        }
+        if let Some(track) = &self.v_fov {
+            camera.v_fov(track.value(time));
+        }
+        if let Some(track) = &self.focus_dist {
+            camera.focus_dist(track.value(time));
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Shapes of the thin lens opening
+
+use std::{path::Path, sync::Arc};
+
+use image::ImageResult;
+use rand::{thread_rng, Rng};
+
+use crate::vec3::{self, Vec3};
+
+/// Shape of the lens opening, which out of focus highlights take on
+#[derive(Clone)]
+pub enum Aperture {
+    /// Round opening of an ideal lens
+    Circle,
+    /// Regular polygon formed by `blades` straight aperture blades, turned `rotation` degrees
+    Polygon {
+        /// Number of blades, and so of corners
+        blades: u32,
+        /// Turn of the polygon in degrees, counterclockwise
+        rotation: f64,
+    },
+    /// Opening given by a grayscale mask
+    Image(Arc<ApertureImage>),
+}
+
+impl Aperture {
+    /// Random point on the aperture, which spans -1 to 1 across its widest
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            Self::Circle => vec3::random_in_unit_disk(),
+            Self::Polygon { blades, rotation } => {
+                vec3::random_in_unit_polygon(*blades, rotation.to_radians())
+            }
+            Self::Image(image) => image.sample(),
+        }
+    }
+}
+
+/// Grayscale mask where white lets light through, sampled in proportion to its brightness
+pub struct ApertureImage {
+    width: usize,
+    height: usize,
+    // Cumulative distribution over the rows, followed by one over the pixels of each row
+    rows: Vec<f64>,
+    columns: Vec<Vec<f64>>,
+}
+
+impl ApertureImage {
+    /// Loads a mask from an image, taking its luminance
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let image = image::open(path)?.to_luma32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+
+        let mut columns = Vec::with_capacity(height);
+        let mut row_weights = Vec::with_capacity(height);
+        for row in image.rows() {
+            let (cdf, total) = cumulative(row.map(|pixel| pixel[0].max(0.0) as f64));
+            columns.push(cdf);
+            row_weights.push(total);
+        }
+        let (rows, _) = cumulative(row_weights.into_iter());
+
+        Ok(Self {
+            width,
+            height,
+            rows,
+            columns,
+        })
+    }
+
+    /// Random point on the mask, with its longer side spanning the unit disk's diameter
+    pub fn sample(&self) -> Vec3 {
+        let mut rng = thread_rng();
+        let y = pick(&self.rows, rng.gen_range(0.0..1.0));
+        let x = pick(&self.columns[y], rng.gen_range(0.0..1.0));
+        let x = (x as f64 + rng.gen_range(0.0..1.0)) / self.width as f64;
+        let y = (y as f64 + rng.gen_range(0.0..1.0)) / self.height as f64;
+
+        // Image rows run top to bottom, so flip them to have the mask appear upright
+        let scale = 2.0 / self.width.max(self.height) as f64;
+        Vec3::from(
+            (x - 0.5) * self.width as f64 * scale,
+            (0.5 - y) * self.height as f64 * scale,
+            0.0,
+        )
+    }
+}
+
+// Normalised running sum of `weights` and their total, uniform when they're all zero
+fn cumulative(weights: impl Iterator<Item = f64>) -> (Vec<f64>, f64) {
+    let mut cdf: Vec<f64> = weights
+        .scan(0.0, |sum, weight| {
+            *sum += weight;
+            Some(*sum)
+        })
+        .collect();
+
+    let total = cdf.last().copied().unwrap_or_default();
+    let count = cdf.len() as f64;
+    for (i, value) in cdf.iter_mut().enumerate() {
+        *value = if total > 0.0 {
+            *value / total
+        } else {
+            (i + 1) as f64 / count
+        };
+    }
+
+    (cdf, total)
+}
+
+// Index of the first entry of a cumulative distribution above `u`
+fn pick(cdf: &[f64], u: f64) -> usize {
+    cdf.partition_point(|&value| value <= u).min(cdf.len() - 1)
+}
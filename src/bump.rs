@@ -0,0 +1,145 @@
+//! Normal and bump mapping of object surfaces
+
+use std::sync::Arc;
+
+use crate::{
+    hit::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    texture::Texture,
+    vec3::Vec3,
+};
+
+/// Perturbs the shading normal of an object by a tangent-space normal map, an RGB texture
+/// encoding the normal's components in [0, 1] with +z pointing away from the surface. Maps
+/// from files should be loaded with `Texture::image_linear`, as they hold vectors, not colors.
+pub struct NormalMap<T: Hittable> {
+    object: T,
+    map: Texture,
+}
+
+impl<T: Hittable> NormalMap<T> {
+    /// Shades `object` with the normals from `map`
+    pub fn new(object: T, map: Texture) -> Self {
+        Self { object, map }
+    }
+}
+
+impl<T: Hittable> Hittable for NormalMap<T> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let mut record = self.object.hit(ray, ray_t)?;
+
+        // Tangent frame of the outward facing surface, so maps look the same from both sides
+        let normal = outward_normal(&record);
+        let tangent = (record.tangent - normal.mul(normal.dot(&record.tangent))).unit();
+        let bitangent = normal.cross(&tangent);
+
+        let texel = self.map.value(record.u, record.v, &record.p);
+        let local = texel.mul(2.0) - Vec3::from(1.0, 1.0, 1.0);
+        let perturbed = tangent.mul(local.x()) + bitangent.mul(local.y()) + normal.mul(local.z());
+        set_normal(&mut record, perturbed);
+
+        Some(record)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        self.object.random(origin)
+    }
+
+    fn animate(&mut self, time: f64) {
+        self.object.animate(time);
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        self.object.materials(materials);
+    }
+}
+
+/// Perturbs the shading normal of an object as if its surface were displaced along the normal
+/// by a height texture times `scale`. Heights from files should be loaded with
+/// `Texture::image_linear`.
+pub struct BumpMap<T: Hittable> {
+    object: T,
+    height: Texture,
+    scale: f64,
+}
+
+impl<T: Hittable> BumpMap<T> {
+    /// Shades `object` as if displaced by `height` times `scale` world units
+    pub fn new(object: T, height: Texture, scale: f64) -> Self {
+        Self {
+            object,
+            height,
+            scale,
+        }
+    }
+
+    fn height(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        self.height.value(u, v, p)[0] * self.scale
+    }
+}
+
+impl<T: Hittable> Hittable for BumpMap<T> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let mut record = self.object.hit(ray, ray_t)?;
+
+        // Forward differences of the height along u and v, stepping the point along with the
+        // coordinates so that solid textures are differentiated too
+        let delta = 1e-3;
+        let (u, v, p) = (record.u, record.v, record.p);
+        let height = self.height(u, v, &p);
+        let du = (self.height(u + delta, v, &(p + record.tangent.mul(delta))) - height) / delta;
+        let dv = (self.height(u, v + delta, &(p + record.bitangent.mul(delta))) - height) / delta;
+
+        let normal = outward_normal(&record);
+        let tangent = record.tangent + normal.mul(du);
+        let bitangent = record.bitangent + normal.mul(dv);
+        let mut perturbed = tangent.cross(&bitangent);
+        if perturbed.dot(&normal) < 0.0 {
+            perturbed = -perturbed;
+        }
+        set_normal(&mut record, perturbed);
+
+        Some(record)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        self.object.random(origin)
+    }
+
+    fn animate(&mut self, time: f64) {
+        self.object.animate(time);
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        self.object.materials(materials);
+    }
+}
+
+fn outward_normal(record: &HitRecord) -> Vec3 {
+    if record.front_face {
+        record.normal
+    } else {
+        -record.normal
+    }
+}
+
+// Replaces the normal with an outward facing `normal`, flipped to face the ray like the
+// geometric one
+fn set_normal(record: &mut HitRecord, normal: Vec3) {
+    if normal.near_zero() {
+        return;
+    }
+
+    let normal = normal.unit();
+    record.normal = if record.front_face { normal } else { -normal };
+}
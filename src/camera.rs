@@ -1,11 +1,96 @@
+//! Cameras and how they render a scene
+
 use image::ImageBuffer;
 use rand::{thread_rng, Rng};
 
-use crate::hit::Hittable;
-use crate::interval::Interval;
+use std::{f64::consts::PI, rc::Rc, sync::Arc};
+
+use crate::animation::CameraAnimation;
+use crate::aperture::Aperture;
+use crate::film::{Aov, Film};
+use crate::hit::{HitRecord, Hittable};
+use crate::integrator::{Background, Integrator, PathTracer};
+use crate::lens::LensSystem;
+use crate::material::Material;
 use crate::ray::Ray;
+use crate::spectrum;
 use crate::vec3::{self, Color, Vec3};
 
+/// How rays leave the camera
+#[derive(Clone, Copy)]
+pub enum Projection {
+    /// Pinhole or thin lens camera with a vertical field of view of `v_fov`
+    Perspective,
+    /// Parallel rays from an image plane `height` world units tall, through `look_from`
+    Orthographic {
+        /// Height of the image plane in world units
+        height: f64,
+    },
+    /// Full sphere of directions mapped to longitude and latitude, best at a 2:1 aspect ratio
+    Equirectangular,
+    /// Six 90 degree views laid out in a 3 by 2 grid, best at a 3:2 aspect ratio. The faces are
+    /// right, left, up on the top row and down, back, front on the bottom one.
+    Cubemap,
+    /// Equidistant fisheye covering `fov` degrees across the circle inscribed in the image
+    Fisheye {
+        /// Angle across the image circle, in degrees
+        fov: f64,
+    },
+}
+
+/// Renders a left and right eye view next to each other for viewing in VR
+#[derive(Clone, Copy)]
+pub struct Stereo {
+    /// Distance between the eyes, in world units, at least zero
+    pub interocular: f64,
+    /// Distance at which the eyes' views line up, objects closer than it appear in front of the
+    /// screen. Must be positive.
+    pub convergence: f64,
+    /// How the eyes' images are arranged
+    pub layout: StereoLayout,
+}
+
+/// Where each eye's image goes in a stereo render
+#[derive(Clone, Copy)]
+pub enum StereoLayout {
+    /// Left eye on the left half of the image
+    SideBySide,
+    /// Left eye on the top half of the image
+    TopBottom,
+}
+
+/// Rectangle of pixels, with `x` and `y` its top left corner
+#[derive(Clone, Copy)]
+pub struct Crop {
+    /// Column of the left edge
+    pub x: u32,
+    /// Row of the top edge
+    pub y: u32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+/// Camera described the way photographers do, see `CameraBuilder::physical`. Scene units are
+/// taken to be metres.
+#[derive(Clone, Copy)]
+pub struct Physical {
+    /// Sensor width and height in millimetres, 36 by 24 for full frame
+    pub sensor: (f64, f64),
+    /// Focal length in millimetres
+    pub focal_length: f64,
+    /// Focal length over the aperture diameter
+    pub f_stop: f64,
+    /// Distance to the plane in focus, in metres
+    pub focus_dist: f64,
+    /// Sensitivity of the sensor
+    pub iso: f64,
+    /// Exposure time in seconds
+    pub shutter: f64,
+}
+
+/// Camera ready to render, set up through a `CameraBuilder`
 #[allow(dead_code)]
 pub struct Camera {
     image_width: u32,
@@ -27,65 +112,352 @@ pub struct Camera {
     focus_dist: f64,
     defocus_disk_u: Vec3,
     defocus_disk_v: Vec3,
+    aperture: Aperture,
+    projection: Projection,
+    lens: Option<Lens>,
+    stereo: Option<Stereo>,
+    background: Background,
+    integrator: Rc<dyn Integrator>,
+    aovs: Vec<Aov>,
+    spectral: bool,
+    exposure: f64,
+}
+
+// Lens system focused in front of a film of the given size, in millimetres
+struct Lens {
+    system: LensSystem,
+    film_width: f64,
+    film_height: f64,
+    // Share of the light reaching the film center, which is exposed as if it got all of it
+    transmission: f64,
 }
 
 impl Camera {
-    pub fn render(&self, world: &impl Hittable) -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
-        let mut imgbuf = ImageBuffer::new(self.image_width, self.image_height);
-        for y in 0..self.image_height {
-            for x in 0..self.image_width {
+    /// Renders the color image, gamma corrected to 8 bits
+    pub fn render(
+        &self,
+        world: &impl Hittable,
+        lights: &impl Hittable,
+    ) -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
+        self.render_film(world, lights).to_image()
+    }
+
+    /// Renders the image, or both eyes' images laid out in one when rendering in stereo
+    pub fn render_film(&self, world: &impl Hittable, lights: &impl Hittable) -> Film {
+        let (width, height) = self.film_size();
+        let crop = Crop {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        self.render_crop(world, lights, &crop)
+    }
+
+    /// Renders only the pixels of the image within `crop`, framed as in the full image. Parts of
+    /// the crop outside the image stay black.
+    pub fn render_crop(&self, world: &impl Hittable, lights: &impl Hittable, crop: &Crop) -> Film {
+        let mut film = Film::new(crop.width, crop.height, &self.aovs);
+        for (eye, x, y) in self.eyes() {
+            // Part of the crop covered by this eye's image
+            let (x0, y0) = (crop.x.max(x), crop.y.max(y));
+            let x1 = crop.x.saturating_add(crop.width).min(x + self.image_width);
+            let y1 = crop
+                .y
+                .saturating_add(crop.height)
+                .min(y + self.image_height);
+            if x0 >= x1 || y0 >= y1 {
+                continue;
+            }
+
+            let region = Crop {
+                x: x0 - x,
+                y: y0 - y,
+                width: x1 - x0,
+                height: y1 - y0,
+            };
+            let eye = self.render_eye(world, lights, eye, &region);
+            film.paste(&eye, x0 - crop.x, y0 - crop.y);
+        }
+
+        film
+    }
+
+    /// Size of the rendered image, which holds both eyes when rendering in stereo
+    pub fn film_size(&self) -> (u32, u32) {
+        let (width, height) = (self.image_width, self.image_height);
+        match self.stereo.map(|stereo| stereo.layout) {
+            None => (width, height),
+            Some(StereoLayout::SideBySide) => (width * 2, height),
+            Some(StereoLayout::TopBottom) => (width, height * 2),
+        }
+    }
+
+    // Offset of each eye from the camera center along with where its image goes in the film
+    fn eyes(&self) -> Vec<(f64, u32, u32)> {
+        let Some(stereo) = self.stereo else {
+            return vec![(0.0, 0, 0)];
+        };
+
+        let (right_x, right_y) = match stereo.layout {
+            StereoLayout::SideBySide => (self.image_width, 0),
+            StereoLayout::TopBottom => (0, self.image_height),
+        };
+        let eye = stereo.interocular / 2.0;
+        vec![(-eye, 0, 0), (eye, right_x, right_y)]
+    }
+
+    // Renders the `crop` of the view from an eye `eye` world units to the right of the camera
+    // center
+    fn render_eye(
+        &self,
+        world: &impl Hittable,
+        lights: &impl Hittable,
+        eye: f64,
+        crop: &Crop,
+    ) -> Film {
+        let mut film = Film::new(crop.width, crop.height, &self.aovs);
+        let materials = materials(world);
+        for j in 0..crop.height {
+            for i in 0..crop.width {
+                let (x, y) = (crop.x + i, crop.y + j);
                 let mut color = Color::from(0.0, 0.0, 0.0);
+                let mut guides = (Color::black(), Color::black(), 0.0);
+                // First hit of the first sample that traced a ray, `None` until there is one
+                let mut first_hit = None;
                 for _ in 0..self.samples {
-                    let ray = self.get_ray(x, y);
-                    color += Self::ray_color(&ray, self.max_depth, world);
+                    // Pixels outside the image circle of a fisheye stay black, as do samples
+                    // the lens system blocks
+                    let Some((ray, weight)) = self.get_ray(x, y, eye) else {
+                        continue;
+                    };
+                    let (radiance, hit) = self.integrator.ray_color(
+                        &ray,
+                        self.max_depth,
+                        world,
+                        lights,
+                        &self.background,
+                    );
+                    let radiance = match ray.wavelength() {
+                        Some(lambda) => spectrum::to_rgb(radiance[0], lambda),
+                        None => radiance,
+                    };
+                    color += radiance.mul(weight);
+
+                    if self.aovs.is_empty() {
+                        continue;
+                    }
+                    if let Some(hit) = &hit {
+                        guides.0 += hit.normal;
+                        guides.1 += hit.mat.albedo(hit);
+                        guides.2 += if hit.front_face { 1.0 } else { 0.0 };
+                    }
+                    if first_hit.is_none() {
+                        first_hit = Some(hit);
+                    }
                 }
 
-                color.scale(1.0 / self.samples as f64);
-                color.to_gamma_space();
+                // Depth and IDs can't be meaningfully averaged so come from the first sample
+                if !self.aovs.is_empty() {
+                    let (depth, object, material) = match first_hit.flatten() {
+                        Some(hit) => (
+                            hit.t,
+                            hit.object_id as f64,
+                            material_id(&hit, &materials) as f64,
+                        ),
+                        None => (f64::INFINITY, 0.0, 0.0),
+                    };
+                    film.set_aov(Aov::Depth, i, j, Color::from(depth, 0.0, 0.0));
+                    film.set_aov(Aov::ObjectId, i, j, Color::from(object, 0.0, 0.0));
+                    film.set_aov(Aov::MaterialId, i, j, Color::from(material, 0.0, 0.0));
+                }
 
-                let pixel = imgbuf.get_pixel_mut(x, y);
+                let scale = 1.0 / self.samples as f64;
+                color.scale(scale * self.exposure);
+                film.set_color(i, j, color);
 
-                *pixel = image::Rgb([color.r(), color.g(), color.b()]);
+                if !self.aovs.is_empty() {
+                    let front_face = guides.2 * scale;
+                    film.set_aov(Aov::Normal, i, j, guides.0.mul(scale));
+                    film.set_aov(Aov::Albedo, i, j, guides.1.mul(scale));
+                    film.set_aov(Aov::FrontFace, i, j, Color::from(front_face, 0.0, 0.0));
+                }
             }
         }
 
-        imgbuf
+        film
     }
 
-    fn ray_color(ray: &Ray, depth: u32, world: &impl Hittable) -> Color {
-        let interval = Interval::new(0.001, f64::INFINITY);
+    // Ray for a sample of pixel `i`, `j` along with the weight of the light it brings back
+    fn get_ray(&self, i: u32, j: u32, eye: f64) -> Option<(Ray, f64)> {
+        let pixel_center =
+            self.pixel00_loc + self.pixel_delta_u.mul(i as f64) + self.pixel_delta_v.mul(j as f64);
+        let pixel_sample = pixel_center + self.pixel_sample_square();
 
-        if depth == 0 {
-            return Color::from(0.0, 0.0, 0.0);
-        }
+        // Position of the sample across the image, each coordinate in [0, 1]
+        let image_sample = || {
+            let mut rng = thread_rng();
+            (
+                (i as f64 + rng.gen_range(0.0..1.0)) / self.image_width as f64,
+                (j as f64 + rng.gen_range(0.0..1.0)) / self.image_height as f64,
+            )
+        };
 
-        if let Some(object) = world.hit(ray, &interval) {
-            match object.mat.scatter(ray, &object) {
-                Some((attenuation, scattered)) => {
-                    attenuation * Self::ray_color(&scattered, depth - 1, world)
+        let center = self.camera_center + self.u.mul(eye);
+        let mut weight = 1.0;
+        let (origin, direction) = match self.projection {
+            Projection::Perspective if self.lens.is_some() => {
+                let (x, y) = image_sample();
+                let (origin, direction, lens_weight) = self.lens_ray(x, y, center)?;
+                weight = lens_weight;
+                (origin, direction)
+            }
+            Projection::Perspective => {
+                let origin = if self.defocus_angle <= 0.0 {
+                    center
+                } else {
+                    self.defocus_disk_sample(center)
+                };
+                (origin, self.converge(pixel_sample, center) - origin)
+            }
+            Projection::Orthographic { .. } => (pixel_sample + self.u.mul(eye), -self.w),
+            Projection::Equirectangular => {
+                let (x, y) = image_sample();
+                let direction = self.equirectangular(x, y);
+                if eye == 0.0 {
+                    (center, direction)
+                } else {
+                    self.omnidirectional_stereo(x, y, direction, eye)
                 }
-                None => Color::black(),
             }
-        } else {
-            let unit_direction = ray.direction().unit();
-            let a = 0.5 * (unit_direction.y() + 1.0);
-            Color::white().mul(1.0 - a) + Color::from(0.5, 0.7, 1.0).mul(a)
+            Projection::Cubemap => {
+                let (x, y) = image_sample();
+                (center, self.cubemap(x, y))
+            }
+            Projection::Fisheye { fov } => {
+                let (x, y) = image_sample();
+                (center, self.fisheye(x, y, fov)?)
+            }
+        };
+        let wavelength = self.spectral.then(spectrum::sample_wavelength);
+
+        let ray = Ray::from(origin, direction).with_wavelength(wavelength);
+        Some((ray, weight))
+    }
+
+    // Ray through the lens system from the film point for image position `x`, `y` and its
+    // weight, or `None` if the lens blocks it. Scene units are taken to be metres.
+    fn lens_ray(&self, x: f64, y: f64, center: Vec3) -> Option<(Vec3, Vec3, f64)> {
+        let lens = self.lens.as_ref()?;
+        // Loading rejects lenses that block the film center, but focusing closer may still
+        if lens.transmission <= 0.0 {
+            return None;
         }
+
+        // The lens flips the image, so the film is sampled upside down and back to front
+        let film = Vec3::from(
+            (0.5 - x) * lens.film_width,
+            (y - 0.5) * lens.film_height,
+            0.0,
+        );
+
+        // Rays are aimed uniformly at the rear element from every film point, so the share the
+        // lens blocks darkens the edges of the image as it does in a real camera. Irradiance
+        // also falls off with the fourth power of the cosine of the ray's angle to the film.
+        let disk = vec3::random_in_unit_disk().mul(lens.system.rear_aperture());
+        let rear = Vec3::from(disk.x(), disk.y(), lens.system.rear_vertex());
+        let (origin, direction) = lens.system.trace_from_film(film, rear - film)?;
+        let cos_theta = (rear - film).unit().z();
+        let weight = cos_theta.powi(4) / lens.transmission;
+
+        let to_world = |a: Vec3| self.view_direction(a.x(), a.y(), a.z());
+        Some((
+            center + to_world(origin).mul(0.001),
+            to_world(direction),
+            weight,
+        ))
     }
 
-    fn get_ray(&self, i: u32, j: u32) -> Ray {
-        let pixel_center =
-            self.pixel00_loc + self.pixel_delta_u.mul(i as f64) + self.pixel_delta_v.mul(j as f64);
-        let pixel_sample = pixel_center + self.pixel_sample_square();
+    // Where the ray from an eye at `center` towards `focus_point`, a point on the focus plane
+    // seen from the camera center, crosses the focus plane. The eyes' frustums are skewed so
+    // they coincide on the convergence plane.
+    fn converge(&self, focus_point: Vec3, center: Vec3) -> Vec3 {
+        let Some(stereo) = self.stereo else {
+            return focus_point;
+        };
+
+        let converged = self.camera_center
+            + (focus_point - self.camera_center).mul(stereo.convergence / self.focus_dist);
+        center + (converged - center).mul(self.focus_dist / stereo.convergence)
+    }
+
+    // Omnidirectional stereo, where the eyes sit on a circle so that every direction is seen
+    // with them side by side. The separation fades towards the poles, where no direction is
+    // sideways.
+    fn omnidirectional_stereo(&self, x: f64, y: f64, direction: Vec3, eye: f64) -> (Vec3, Vec3) {
+        let longitude = (x - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - y) * PI;
+        let sideways = self.view_direction(longitude.cos(), 0.0, -longitude.sin());
+        let origin = self.camera_center + sideways.mul(eye * latitude.cos());
+
+        let convergence = self
+            .stereo
+            .map_or(f64::INFINITY, |stereo| stereo.convergence);
+        let target = self.camera_center + direction.mul(convergence);
+        (origin, target - origin)
+    }
+
+    // Direction in world space from the camera's right, up and forward components
+    fn view_direction(&self, right: f64, up: f64, forward: f64) -> Vec3 {
+        self.u.mul(right) + self.v.mul(up) - self.w.mul(forward)
+    }
+
+    fn equirectangular(&self, x: f64, y: f64) -> Vec3 {
+        let longitude = (x - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - y) * PI;
+
+        self.view_direction(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            latitude.cos() * longitude.cos(),
+        )
+    }
 
-        let origin = if self.defocus_angle <= 0.0 {
-            self.camera_center
-        } else {
-            self.defocus_disk_sample()
+    fn cubemap(&self, x: f64, y: f64) -> Vec3 {
+        let column = ((x * 3.0) as usize).min(2);
+        let row = ((y * 2.0) as usize).min(1);
+        // Coordinates across the face, with b pointing up
+        let a = 2.0 * (x * 3.0 - column as f64) - 1.0;
+        let b = 1.0 - 2.0 * (y * 2.0 - row as f64);
+
+        let (right, up, forward) = match (row, column) {
+            (0, 0) => (1.0, b, -a),
+            (0, 1) => (-1.0, b, a),
+            (0, 2) => (a, 1.0, -b),
+            (1, 0) => (a, -1.0, b),
+            (1, 1) => (-a, b, -1.0),
+            _ => (a, b, 1.0),
         };
-        let direction = pixel_sample - origin;
+        self.view_direction(right, up, forward)
+    }
+
+    fn fisheye(&self, x: f64, y: f64, fov: f64) -> Option<Vec3> {
+        // Offset from the image center in units of the image circle's radius
+        let radius = self.image_width.min(self.image_height) as f64 / 2.0;
+        let dx = (x - 0.5) * self.image_width as f64 / radius;
+        let dy = (0.5 - y) * self.image_height as f64 / radius;
+        let r = (dx * dx + dy * dy).sqrt();
+        if r > 1.0 {
+            return None;
+        }
 
-        Ray::from(origin, direction)
+        let theta = r * fov.to_radians() / 2.0;
+        let phi = dy.atan2(dx);
+        Some(self.view_direction(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        ))
     }
 
     fn pixel_sample_square(&self) -> Vec3 {
@@ -96,13 +468,15 @@ impl Camera {
         self.pixel_delta_u.mul(px) + self.pixel_delta_v.mul(py)
     }
 
-    fn defocus_disk_sample(&self) -> Vec3 {
-        let p = vec3::random_in_unit_disk();
-        self.camera_center + (self.defocus_disk_u.mul(p[0])) + (self.defocus_disk_v.mul(p[1]))
+    fn defocus_disk_sample(&self, center: Vec3) -> Vec3 {
+        let p = self.aperture.sample();
+        center + (self.defocus_disk_u.mul(p[0])) + (self.defocus_disk_v.mul(p[1]))
     }
 }
 
-#[allow(dead_code)]
+/// Camera settings, starting from a 400 pixel wide 16:9 pinhole camera at the origin looking
+/// down -z with a 90 degree field of view, path tracing 100 samples per pixel under the sky
+#[derive(Clone)]
 pub struct CameraBuilder {
     image_width: u32,
     aspect_ratio: f64,
@@ -114,63 +488,204 @@ pub struct CameraBuilder {
     v_up: Vec3,
     focus_dist: f64,
     defocus_angle: f64,
+    aperture: Aperture,
+    projection: Projection,
+    // Lens system and the film diagonal in millimetres
+    lens: Option<(LensSystem, f64)>,
+    stereo: Option<Stereo>,
+    background: Background,
+    integrator: Rc<dyn Integrator>,
+    aovs: Vec<Aov>,
+    spectral: bool,
+    exposure: f64,
+    animation: Option<CameraAnimation>,
+    time: f64,
+    orbit: f64,
 }
 
-#[allow(dead_code)]
 impl CameraBuilder {
+    /// Default settings, see `CameraBuilder`
     pub fn new() -> CameraBuilder {
         CameraBuilder {
             image_width: 400,
             aspect_ratio: 16.0 / 9.0,
             samples: 100,
-            max_depth: 50,
+            max_depth: 1000,
             v_fov: 90.0,
             look_from: Vec3::from(0.0, 0.0, -1.0),
             look_at: Vec3::from(0.0, 0.0, 0.0),
             v_up: Vec3::from(0.0, 1.0, 0.0),
             focus_dist: 10.0,
             defocus_angle: 0.0,
+            aperture: Aperture::Circle,
+            projection: Projection::Perspective,
+            lens: None,
+            stereo: None,
+            background: Background::Sky,
+            integrator: Rc::new(PathTracer::default()),
+            aovs: Vec::new(),
+            spectral: false,
+            exposure: 1.0,
+            animation: None,
+            time: 0.0,
+            orbit: 0.0,
         }
     }
 
+    /// Builder for the thin lens camera equivalent to a physical one
+    pub fn physical(physical: &Physical) -> CameraBuilder {
+        let (width, height) = physical.sensor;
+        let focal_length = physical.focal_length / 1000.0;
+        let focus_dist = physical.focus_dist.max(focal_length * 1.001);
+
+        // Focusing moves the lens away from the sensor, which narrows the field of view
+        let image_dist = focal_length * focus_dist / (focus_dist - focal_length);
+        let v_fov = 2.0 * (height / 1000.0 / 2.0 / image_dist).atan();
+        let aperture_radius = focal_length / physical.f_stop / 2.0;
+        let defocus_angle = 2.0 * (aperture_radius / focus_dist).atan();
+
+        let mut builder = Self::new();
+        builder.aspect_ratio(width / height);
+        builder.v_fov(v_fov.to_degrees());
+        builder.focus_dist(focus_dist);
+        builder.defocus_angle(defocus_angle.to_degrees());
+        // By the sunny 16 rule f/16 at 1/ISO seconds is correct for daylight, which scenes
+        // model as a sky of radiance about one
+        builder.exposure(physical.shutter * physical.iso * 256.0 / physical.f_stop.powi(2));
+
+        builder
+    }
+
+    /// Width of the rendered image in pixels, the height follows from the aspect ratio
     pub fn image_width(&mut self, image_width: u32) {
         self.image_width = image_width;
     }
+    /// Image width over height
     pub fn aspect_ratio(&mut self, aspect_ratio: f64) {
         self.aspect_ratio = aspect_ratio;
     }
+    /// Number of rays traced per pixel
     pub fn samples(&mut self, samples: u32) {
         self.samples = samples;
     }
+    /// Number of bounces after which paths are cut off, a safety cap that darkens the image if
+    /// paths reach it before Russian roulette ends them
     pub fn max_depth(&mut self, max_depth: u32) {
         self.max_depth = max_depth;
     }
+    /// Vertical field of view of perspective cameras, in degrees
     pub fn v_fov(&mut self, v_fov: f64) {
         self.v_fov = v_fov;
     }
+    /// Point the camera looks at
     pub fn look_at(&mut self, look_at: Vec3) {
         self.look_at = look_at;
     }
+    /// Position of the camera
     pub fn look_from(&mut self, look_from: Vec3) {
         self.look_from = look_from;
     }
+    /// Direction that appears upwards in the image
     pub fn v_up(&mut self, v_up: Vec3) {
         self.v_up = v_up;
     }
+    /// Distance from `look_from` to the plane in perfect focus, in world units
     pub fn focus_dist(&mut self, focus_dist: f64) {
         self.focus_dist = focus_dist;
     }
+    /// Angle in degrees of the cone from the plane in focus to the lens, zero for a pinhole
+    /// camera with everything in focus
     pub fn defocus_angle(&mut self, defocus_angle: f64) {
         self.defocus_angle = defocus_angle
     }
+    /// Shape of the thin lens, `defocus_angle` is the cone spanned by its widest extent
+    pub fn aperture(&mut self, aperture: Aperture) {
+        self.aperture = aperture;
+    }
+    /// How rays leave the camera, perspective by default
+    pub fn projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+    /// Replaces the thin lens of perspective cameras with a lens system in front of a film
+    /// `film_diagonal` millimetres across, focused at `focus_dist`. The field of view and depth
+    /// of field then follow from the lens instead of `v_fov` and `defocus_angle`.
+    pub fn lens_system(&mut self, system: LensSystem, film_diagonal: f64) {
+        self.lens = Some((system, film_diagonal));
+    }
+    /// Render both eyes into one image, each `image_width` wide. Only perspective and
+    /// equirectangular projections support stereo.
+    pub fn stereo(&mut self, stereo: Stereo) {
+        self.stereo = Some(stereo);
+    }
+    /// Color of the light from rays that leave the scene, instead of the sky
+    pub fn background(&mut self, background: Color) {
+        self.background = Background::Solid(background);
+    }
+    /// How light is gathered along camera rays, path tracing by default
+    pub fn integrator(&mut self, integrator: Rc<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+    /// Auxiliary buffers to render along with the color
+    pub fn aovs(&mut self, aovs: &[Aov]) {
+        self.aovs = aovs.to_vec();
+    }
+    /// Trace a single wavelength per sample instead of RGB, which dispersive materials need
+    pub fn spectral(&mut self, spectral: bool) {
+        self.spectral = spectral;
+    }
+    /// Scale applied to the radiance reaching the film
+    pub fn exposure(&mut self, exposure: f64) {
+        self.exposure = exposure;
+    }
+
+    /// Keyframes for the camera's parameters, which take precedence over their settings
+    pub fn animation(&mut self, animation: CameraAnimation) {
+        self.animation = Some(animation);
+    }
+    /// Time in seconds at which animated parameters are taken
+    pub fn time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Swings `look_from` by `angle` degrees around the `v_up` axis through `look_at`, keeping
+    /// its distance and elevation
+    pub fn orbit(&mut self, angle: f64) {
+        self.orbit = angle;
+    }
 
+    /// Camera with these settings, posed at `time` if animated
     pub fn build(&self) -> Camera {
         self.into()
     }
 }
 
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl From<&CameraBuilder> for Camera {
     fn from(input: &CameraBuilder) -> Self {
+        if input.animation.is_some() || input.orbit != 0.0 {
+            let mut posed = input.clone();
+            (posed.animation, posed.orbit) = (None, 0.0);
+            if let Some(animation) = &input.animation {
+                animation.apply(&mut posed, input.time);
+            }
+
+            // Rodrigues' rotation of the offset from the target about the up axis
+            let axis = posed.v_up.unit();
+            let offset = posed.look_from - posed.look_at;
+            let (sin, cos) = input.orbit.to_radians().sin_cos();
+            let offset = offset.mul(cos)
+                + axis.cross(&offset).mul(sin)
+                + axis.mul(axis.dot(&offset) * (1.0 - cos));
+            posed.look_from = posed.look_at + offset;
+
+            return (&posed).into();
+        }
+
         let mut height = (input.image_width as f64 / input.aspect_ratio) as u32;
         if height < 1 {
             height = 1
@@ -178,10 +693,16 @@ impl From<&CameraBuilder> for Camera {
 
         let camera_center = input.look_from;
 
-        // Viewport Dimensions
-        let theta = input.v_fov.to_radians();
-        let h = (theta / 2.0).tan();
-        let viewport_height = 2.0 * h * input.focus_dist;
+        // Viewport Dimensions, the viewport of orthographic cameras lies on the camera center
+        // rather than the focus plane. Panoramic projections don't use one.
+        let (viewport_height, viewport_dist) = match input.projection {
+            Projection::Orthographic { height } => (height, 0.0),
+            _ => {
+                let theta = input.v_fov.to_radians();
+                let h = (theta / 2.0).tan();
+                (2.0 * h * input.focus_dist, input.focus_dist)
+            }
+        };
         let viewport_width = viewport_height * (input.image_width as f64 / height as f64);
 
         // Calculate the u, v, w unit basis vectors for the camera coordinate frame
@@ -199,7 +720,7 @@ impl From<&CameraBuilder> for Camera {
 
         // Calculate location of upper left pixel
         let viewport_upper_left =
-            camera_center - w.mul(input.focus_dist) - viewport_u.div(2.0) - viewport_v.div(2.0);
+            camera_center - w.mul(viewport_dist) - viewport_u.div(2.0) - viewport_v.div(2.0);
         let pixel00_loc = viewport_upper_left + (pixel_delta_u + pixel_delta_v).mul(0.5);
 
         let defocus_radius = (input.defocus_angle / 2.0).to_radians().tan() * input.focus_dist;
@@ -223,6 +744,52 @@ impl From<&CameraBuilder> for Camera {
             focus_dist: input.focus_dist,
             defocus_disk_u: u.mul(defocus_radius),
             defocus_disk_v: v.mul(defocus_radius),
+            aperture: input.aperture.clone(),
+            projection: input.projection,
+            lens: input.lens.as_ref().map(|(system, film_diagonal)| {
+                let mut system = system.clone();
+                system.focus(input.focus_dist * 1000.0);
+                let transmission = system.transmission();
+
+                // The film takes on the image's aspect ratio
+                let diagonal = (1.0 + input.aspect_ratio * input.aspect_ratio).sqrt();
+                Lens {
+                    system,
+                    film_width: film_diagonal * input.aspect_ratio / diagonal,
+                    film_height: film_diagonal / diagonal,
+                    transmission,
+                }
+            }),
+            stereo: input.stereo,
+            background: input.background,
+            integrator: input.integrator.clone(),
+            aovs: input.aovs.clone(),
+            spectral: input.spectral,
+            exposure: input.exposure,
         }
     }
 }
+
+// Distinct materials of `world` in the order it lists them, which material IDs count through
+fn materials(world: &impl Hittable) -> Vec<Arc<dyn Material>> {
+    let mut all = Vec::new();
+    world.materials(&mut all);
+
+    let mut distinct: Vec<Arc<dyn Material>> = Vec::new();
+    for material in all {
+        if !distinct.iter().any(|other| Arc::ptr_eq(other, &material)) {
+            distinct.push(material);
+        }
+    }
+    distinct
+}
+
+// Identifies the material of a hit by its position among `materials` plus one, so IDs stay the
+// same across runs and frames of the same scene. Zero is left for misses and materials the
+// world doesn't list.
+fn material_id(record: &HitRecord, materials: &[Arc<dyn Material>]) -> u32 {
+    materials
+        .iter()
+        .position(|material| Arc::ptr_eq(material, &record.mat))
+        .map_or(0, |index| index as u32 + 1)
+}
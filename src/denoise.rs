@@ -0,0 +1,112 @@
+//! Denoising of rendered films guided by their AOVs
+
+use crate::{
+    film::{Aov, Film},
+    vec3::Color,
+};
+
+// 5x5 B3-spline kernel weights, applied with growing gaps between taps
+const KERNEL: [f64; 3] = [3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Edge-avoiding à-trous wavelet filter: a joint bilateral filter guided by the albedo and normal
+/// AOVs, run over several passes with increasing tap spacing. Lighting is filtered with the
+/// albedo divided out so texture detail isn't blurred.
+pub struct Denoiser {
+    /// How far apart neighbouring pixels' lighting may be and still get blended, zero leaves
+    /// the image as it is
+    pub strength: f64,
+    /// Number of passes, each doubling the spacing between taps
+    pub iterations: u32,
+}
+
+impl Denoiser {
+    /// Denoiser of the given `strength` running five passes
+    pub fn new(strength: f64) -> Self {
+        Self {
+            strength,
+            iterations: 5,
+        }
+    }
+
+    /// Returns a copy of `film` with its color filtered. Requires the normal and albedo AOVs.
+    pub fn denoise(&self, film: &Film) -> Option<Film> {
+        let normals = film.aov(Aov::Normal)?;
+        let albedo = film.aov(Aov::Albedo)?;
+        let (width, height) = (film.width() as i64, film.height() as i64);
+
+        let mut illumination: Vec<Color> = (0..film.height())
+            .flat_map(|y| (0..film.width()).map(move |x| (x, y)))
+            .zip(albedo)
+            .map(|((x, y), albedo)| film.color(x, y) / demodulation(albedo))
+            .collect();
+
+        if self.strength > 0.0 {
+            let sigma_color = self.strength;
+            for iteration in 0..self.iterations {
+                let step = 1 << iteration;
+                let mut filtered = illumination.clone();
+
+                for y in 0..height {
+                    for x in 0..width {
+                        let index = (y * width + x) as usize;
+                        let center = illumination[index];
+
+                        let mut sum = Color::black();
+                        let mut total = 0.0;
+                        for dy in -2..=2_i64 {
+                            for dx in -2..=2_i64 {
+                                let (sx, sy) = (x + dx * step, y + dy * step);
+                                if sx < 0 || sy < 0 || sx >= width || sy >= height {
+                                    continue;
+                                }
+                                let sample = (sy * width + sx) as usize;
+
+                                let kernel = KERNEL[dx.unsigned_abs() as usize]
+                                    * KERNEL[dy.unsigned_abs() as usize];
+                                let normal = normals[index].dot(&normals[sample]).max(0.0).powi(32);
+                                let albedo = (-(albedo[index] - albedo[sample]).length_squared()
+                                    / 0.01)
+                                    .exp();
+                                // The color tolerance is halved each pass as the image smooths out
+                                let color_sigma = sigma_color / (1 << iteration) as f64;
+                                let color = (-(center - illumination[sample]).length_squared()
+                                    / (color_sigma * color_sigma))
+                                    .exp();
+
+                                let weight = kernel * normal * albedo * color;
+                                sum += illumination[sample].mul(weight);
+                                total += weight;
+                            }
+                        }
+
+                        if total > 0.0 {
+                            filtered[index] = sum.div(total);
+                        }
+                    }
+                }
+
+                illumination = filtered;
+            }
+        }
+
+        let mut denoised = film.clone();
+        for y in 0..film.height() {
+            for x in 0..film.width() {
+                let index = (y * film.width() + x) as usize;
+                let color = illumination[index] * demodulation(&albedo[index]);
+                denoised.set_color(x, y, color);
+            }
+        }
+
+        Some(denoised)
+    }
+}
+
+// Albedo with black channels clamped so lighting can be divided out and restored losslessly
+fn demodulation(albedo: &Color) -> Color {
+    Color::from(
+        albedo[0].max(0.01),
+        albedo[1].max(0.01),
+        albedo[2].max(0.01),
+    )
+}
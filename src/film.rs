@@ -0,0 +1,294 @@
+//! Framebuffers that renders are written to
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, ImageAttributes, IntegerBounds, Layer,
+    LayerAttributes, SmallVec, Vec2, WritableImage,
+};
+use image::{DynamicImage, ImageBuffer, ImageResult};
+
+use crate::vec3::Color;
+
+/// Auxiliary per-pixel buffers rendered alongside the color image
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Aov {
+    /// Ray parameter of the first hit, infinite for misses
+    Depth,
+    /// Shading normal at the first hit, averaged over the pixel
+    Normal,
+    /// Albedo of the material at the first hit, averaged over the pixel
+    Albedo,
+    /// `HitRecord::object_id` of the first hit
+    ObjectId,
+    /// Number of the first hit's material among those `Hittable::materials` lists, zero for
+    /// misses
+    MaterialId,
+    /// Share of the pixel's first hits on the front of a surface
+    FrontFace,
+}
+
+impl Aov {
+    /// Every AOV
+    pub const ALL: [Aov; 6] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::FrontFace,
+    ];
+
+    /// Name used on the command line and in output file names
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Depth => "depth",
+            Self::Normal => "normal",
+            Self::Albedo => "albedo",
+            Self::ObjectId => "object",
+            Self::MaterialId => "material",
+            Self::FrontFace => "frontface",
+        }
+    }
+
+    /// AOV called `name`, if there is one
+    pub fn from_name(name: &str) -> Option<Aov> {
+        Self::ALL.into_iter().find(|aov| aov.name() == name)
+    }
+
+    fn channels(&self) -> &'static [&'static str] {
+        match self {
+            Self::Depth => &["Z"],
+            Self::Normal => &["X", "Y", "Z"],
+            Self::Albedo => &["R", "G", "B"],
+            Self::ObjectId | Self::MaterialId => &["id"],
+            Self::FrontFace => &["A"],
+        }
+    }
+}
+
+/// Linear float framebuffer holding the beauty pass and any requested AOVs. Single channel AOVs
+/// are stored in the first component of each pixel.
+#[derive(Clone)]
+pub struct Film {
+    width: u32,
+    height: u32,
+    color: Vec<Color>,
+    aovs: Vec<(Aov, Vec<Color>)>,
+}
+
+impl Film {
+    /// Black film with buffers for `aovs`
+    pub fn new(width: u32, height: u32, aovs: &[Aov]) -> Self {
+        let len = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            color: vec![Color::black(); len],
+            aovs: aovs
+                .iter()
+                .map(|aov| (*aov, vec![Color::black(); len]))
+                .collect(),
+        }
+    }
+
+    /// Reads a previous render back in, an EXR as is and other images undoing the gamma of
+    /// `to_image`. AOVs aren't read.
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let image = image::open(path)?;
+        let linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let image = image.into_rgb32f();
+
+        let mut film = Self::new(image.width(), image.height(), &[]);
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let mut color = Color::from(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+            if !linear {
+                color = color * color;
+            }
+            film.set_color(x, y, color);
+        }
+
+        Ok(film)
+    }
+
+    /// Width in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Color of the pixel at `x`, `y`
+    pub fn color(&self, x: u32, y: u32) -> Color {
+        self.color[self.index(x, y)]
+    }
+
+    /// Sets the color of the pixel at `x`, `y`
+    pub fn set_color(&mut self, x: u32, y: u32, color: Color) {
+        let index = self.index(x, y);
+        self.color[index] = color;
+    }
+
+    /// Buffer of `aov` in row order, if the film holds it
+    pub fn aov(&self, aov: Aov) -> Option<&[Color]> {
+        self.aovs
+            .iter()
+            .find(|(kind, _)| *kind == aov)
+            .map(|(_, buffer)| buffer.as_slice())
+    }
+
+    /// Sets the value of `aov` at `x`, `y`, ignored if the film doesn't hold it
+    pub fn set_aov(&mut self, aov: Aov, x: u32, y: u32, value: Color) {
+        let index = self.index(x, y);
+        if let Some((_, buffer)) = self.aovs.iter_mut().find(|(kind, _)| *kind == aov) {
+            buffer[index] = value;
+        }
+    }
+
+    /// Copies `film` into this one with its top left corner at `x`, `y`, along with the AOVs
+    /// both have
+    pub fn paste(&mut self, film: &Film, x: u32, y: u32) {
+        for j in 0..film.height.min(self.height.saturating_sub(y)) {
+            for i in 0..film.width.min(self.width.saturating_sub(x)) {
+                let (to, from) = (self.index(x + i, y + j), film.index(i, j));
+                self.color[to] = film.color[from];
+                for (aov, values) in &mut self.aovs {
+                    if let Some(source) = film.aov(*aov) {
+                        values[to] = source[from];
+                    }
+                }
+            }
+        }
+    }
+
+    /// Color image, gamma corrected to 8 bits
+    pub fn to_image(&self) -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let mut color = self.color(x, y);
+            color.to_gamma_space();
+            image::Rgb([color.r(), color.g(), color.b()])
+        })
+    }
+
+    /// 8-bit visualisation of an AOV: depth is normalised to the furthest hit, normals are mapped
+    /// from [-1, 1] and IDs are given arbitrary distinct colors
+    pub fn aov_image(&self, aov: Aov) -> Option<ImageBuffer<image::Rgb<u8>, Vec<u8>>> {
+        let buffer = self.aov(aov)?;
+        let max_depth = buffer
+            .iter()
+            .map(|value| value[0])
+            .filter(|depth| depth.is_finite())
+            .fold(0.0, f64::max);
+
+        Some(ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let value = buffer[self.index(x, y)];
+            let mut color = match aov {
+                Aov::Depth if value[0].is_finite() && max_depth > 0.0 => {
+                    let depth = value[0] / max_depth;
+                    Color::from(depth, depth, depth)
+                }
+                Aov::Depth => Color::black(),
+                Aov::Normal => (value + Color::white()).mul(0.5),
+                Aov::Albedo => {
+                    let mut albedo = value;
+                    albedo.to_gamma_space();
+                    albedo
+                }
+                Aov::ObjectId | Aov::MaterialId => id_color(value[0]),
+                Aov::FrontFace => Color::from(value[0], value[0], value[0]),
+            };
+            for channel in color.iter_mut() {
+                *channel = channel.clamp(0.0, 1.0);
+            }
+            image::Rgb([color.r(), color.g(), color.b()])
+        }))
+    }
+
+    /// Writes the beauty pass and every AOV as layers of one multi-layer EXR
+    pub fn save_exr(&self, path: impl AsRef<Path>) -> exr::error::Result<()> {
+        let size = Vec2(self.width as usize, self.height as usize);
+
+        let mut layers = vec![Self::exr_layer(
+            size,
+            "",
+            &["R", "G", "B"],
+            &self.color,
+            false,
+        )];
+        for (aov, buffer) in self.aovs.iter() {
+            let integer = matches!(aov, Aov::ObjectId | Aov::MaterialId);
+            layers.push(Self::exr_layer(
+                size,
+                aov.name(),
+                aov.channels(),
+                buffer,
+                integer,
+            ));
+        }
+
+        let image = Image::from_layers(
+            ImageAttributes::new(IntegerBounds::from_dimensions(size)),
+            layers,
+        );
+        image.write().to_file(path)
+    }
+
+    fn exr_layer(
+        size: Vec2<usize>,
+        name: &str,
+        channels: &[&str],
+        buffer: &[Color],
+        integer: bool,
+    ) -> Layer<AnyChannels<FlatSamples>> {
+        let channels = channels
+            .iter()
+            .enumerate()
+            .map(|(component, channel)| {
+                let values = buffer.iter().map(|value| value[component]);
+                let samples = if integer {
+                    FlatSamples::U32(values.map(|value| value as u32).collect())
+                } else {
+                    FlatSamples::F32(values.map(|value| value as f32).collect())
+                };
+                AnyChannel::new(*channel, samples)
+            })
+            .collect();
+
+        Layer::new(
+            size,
+            LayerAttributes::named(name),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(SmallVec::from_vec(channels)),
+        )
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.width + x) as usize
+    }
+}
+
+fn id_color(id: f64) -> Color {
+    if id == 0.0 {
+        return Color::black();
+    }
+
+    let mut hasher = DefaultHasher::new();
+    id.to_bits().hash(&mut hasher);
+    let hash = hasher.finish();
+
+    Color::from(
+        (hash & 0xff) as f64 / 255.0,
+        ((hash >> 8) & 0xff) as f64 / 255.0,
+        ((hash >> 16) & 0xff) as f64 / 255.0,
+    )
+}
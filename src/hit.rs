@@ -1,21 +1,94 @@
+//! Ray intersections with objects and lists of them
+
 use crate::{interval::Interval, material::Material, ray::Ray, vec3::Vec3};
-use std::ops::{Deref, DerefMut};
+use rand::{thread_rng, Rng};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
+/// Anything rays can hit, which makes up the world and the lights sampled in it
 pub trait Hittable {
+    /// Closest hit along `ray` with its parameter within `ray_t`
     fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord>;
+
+    /// Solid angle density of `random` sampling `direction` from `origin`, used for light sampling
+    fn pdf_value(&self, _origin: &Vec3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    /// Random direction from `origin` towards the surface of the object
+    fn random(&self, _origin: &Vec3) -> Vec3 {
+        Vec3::from(1.0, 0.0, 0.0)
+    }
+
+    /// Poses animated objects as they are `time` seconds into the animation
+    fn animate(&mut self, _time: f64) {}
+
+    /// Appends the materials the object is made of, in a fixed order, which the material AOV
+    /// numbers them by
+    fn materials(&self, _materials: &mut Vec<Arc<dyn Material>>) {}
+}
+
+impl<T: Hittable + ?Sized> Hittable for Box<T> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        (**self).hit(ray, ray_t)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        (**self).random(origin)
+    }
+
+    fn animate(&mut self, time: f64) {
+        (**self).animate(time)
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        (**self).materials(materials)
+    }
 }
 
+/// Where and how a ray hit an object
 #[derive(Clone)]
 pub struct HitRecord {
+    /// Point that was hit
     pub p: Vec3,
+    /// Unit shading normal, facing against the ray
     pub normal: Vec3,
-    pub mat: Material,
+    /// Material of the surface at the hit
+    pub mat: Arc<dyn Material>,
+    /// Ray parameter of the hit
     pub t: f64,
+    /// Whether the ray hit the outside of the surface
     pub front_face: bool,
+    /// Horizontal surface coordinate of the hit, in [0, 1]
+    pub u: f64,
+    /// Vertical surface coordinate of the hit, in [0, 1]
+    pub v: f64,
+    /// Derivative of the hit point along u, not normalised. Together with `bitangent` it spans
+    /// the tangent plane of the geometric surface.
+    pub tangent: Vec3,
+    /// Derivative of the hit point along v, not normalised
+    pub bitangent: Vec3,
+    /// Index of the object within the outermost `HitList` plus one, zero is left for misses
+    pub object_id: u32,
 }
 
 impl HitRecord {
-    pub fn new(ray: &Ray, p: Vec3, normal: Vec3, t: f64, mat: Material) -> Self {
+    /// Record of a hit along `ray`, turning the outward `normal` to face the ray
+    pub fn new(
+        ray: &Ray,
+        p: Vec3,
+        normal: Vec3,
+        t: f64,
+        (u, v): (f64, f64),
+        (tangent, bitangent): (Vec3, Vec3),
+        mat: Arc<dyn Material>,
+    ) -> Self {
         let front_face = ray.direction().dot(&normal) < 0.0;
         let mut normal = normal;
         if !front_face {
@@ -28,18 +101,39 @@ impl HitRecord {
             mat,
             t,
             front_face,
+            u,
+            v,
+            tangent,
+            bitangent,
+            object_id: 0,
         }
     }
 }
 
+/// Group of objects hit as one, reporting the closest hit among them. Lights sampled through
+/// a list are picked uniformly.
 pub struct HitList<T: Hittable>(Vec<T>);
 
 impl<T: Hittable> HitList<T> {
+    /// Empty list
     pub fn new() -> Self {
         Self(Vec::new())
     }
 }
 
+impl<T: Hittable> Default for HitList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HitList<Box<dyn Hittable>> {
+    /// Adds `object` to the list, boxing it
+    pub fn add(&mut self, object: impl Hittable + 'static) {
+        self.0.push(Box::new(object));
+    }
+}
+
 impl<T: Hittable> Deref for HitList<T> {
     type Target = Vec<T>;
 
@@ -60,13 +154,49 @@ impl<T: Hittable> Hittable for HitList<T> {
 
         let mut record = None;
 
-        for object in self.0.iter() {
-            if let Some(object) = object.hit(ray, &ray_t) {
-                record = Some(object.clone());
+        for (index, object) in self.0.iter().enumerate() {
+            if let Some(mut object) = object.hit(ray, &ray_t) {
+                object.object_id = index as u32 + 1;
                 ray_t.max = object.t;
+                record = Some(object);
             }
         }
 
         record
     }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        if self.0.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f64 = self
+            .0
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+
+        sum / self.0.len() as f64
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        if self.0.is_empty() {
+            return Vec3::from(1.0, 0.0, 0.0);
+        }
+
+        let index = thread_rng().gen_range(0..self.0.len());
+        self.0[index].random(origin)
+    }
+
+    fn animate(&mut self, time: f64) {
+        for object in &mut self.0 {
+            object.animate(time);
+        }
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        for object in &self.0 {
+            object.materials(materials);
+        }
+    }
 }
@@ -0,0 +1,346 @@
+//! Algorithms gathering the light arriving along camera rays
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    hit::{HitRecord, Hittable},
+    interval::Interval,
+    onb::Onb,
+    ray::Ray,
+    spectrum,
+    vec3::{self, Color},
+};
+
+/// Computes the radiance arriving along a camera ray. For rays carrying a wavelength this is
+/// the spectral radiance at that wavelength, repeated in every channel.
+pub trait Integrator {
+    /// Radiance arriving along `ray` from `world`, sampling `lights` directly, with paths cut off
+    /// after `depth` bounces. Also returns the first surface the ray hit, which the AOVs are
+    /// taken from.
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        depth: u32,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        background: &Background,
+    ) -> (Color, Option<HitRecord>);
+}
+
+/// Light arriving from rays that leave the scene
+#[derive(Clone, Copy)]
+pub enum Background {
+    /// Gradient from white at the horizon to blue overhead
+    Sky,
+    /// Same color in every direction
+    Solid(Color),
+}
+
+impl Background {
+    /// Radiance arriving along `ray`
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Self::Sky => {
+                let unit_direction = ray.direction().unit();
+                let a = 0.5 * (unit_direction.y() + 1.0);
+                Color::white().mul(1.0 - a) + Color::from(0.5, 0.7, 1.0).mul(a)
+            }
+            Self::Solid(color) => *color,
+        }
+    }
+}
+
+/// Unidirectional path tracer with next event estimation, taking random walks through scattering
+/// media. Paths are terminated by Russian roulette once they've bounced `rr_depth` times.
+/// `depth` is only a safety cap against endless paths, paths reaching it are cut off and lose
+/// the light they would have gathered, so it should be well beyond the lengths roulette allows.
+pub struct PathTracer {
+    /// Number of bounces before Russian roulette starts
+    pub rr_depth: u32,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self { rr_depth: 3 }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        depth: u32,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        background: &Background,
+    ) -> (Color, Option<HitRecord>) {
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut rng = thread_rng();
+
+        let mut first_hit = None;
+        let mut color = Color::black();
+        let mut throughput = Color::white();
+        let mut ray = *ray;
+        // Density the previous bounce sampled `ray` with, or `None` for camera rays and specular
+        // bounces, where emission can't also be reached by light sampling
+        let mut bsdf_pdf = None;
+
+        for bounce in 0..depth {
+            let Some(object) = world.hit(&ray, &interval) else {
+                color += throughput * spectral(&ray, background.color(&ray));
+                break;
+            };
+            if bounce == 0 {
+                first_hit = Some(object.clone());
+            }
+            let (scattered, weight) = medium_interaction(&ray, &object);
+            throughput *= spectral(&ray, weight);
+
+            let in_medium = scattered.is_some();
+            let next = match scattered {
+                // Scattered inside a medium, whose boundary lights can't be sampled through
+                Some(next) => {
+                    bsdf_pdf = None;
+                    next
+                }
+                None => {
+                    let mut emitted = spectral(&ray, object.mat.emitted(&object));
+                    if let Some(bsdf_pdf) = bsdf_pdf {
+                        if !emitted.is_black() {
+                            let light_pdf = lights.pdf_value(ray.origin(), ray.direction());
+                            emitted = emitted.mul(power_heuristic(bsdf_pdf, light_pdf));
+                        }
+                    }
+                    color += throughput * emitted;
+
+                    let Some(sample) = object.mat.sample(&ray, &object) else {
+                        break;
+                    };
+
+                    bsdf_pdf = sample.pdf;
+                    if bsdf_pdf.is_some() {
+                        color += throughput * sample_light(&ray, &object, world, lights);
+                    }
+
+                    throughput *= spectral(&ray, sample.weight);
+                    sample.ray
+                }
+            };
+            ray = next.with_wavelength(ray.wavelength());
+
+            if bounce + 1 >= self.rr_depth {
+                // Random walks through bright media take hundreds of steps while barely losing
+                // any throughput. Capping their survival would compound into weights of
+                // 1 / 0.95^n on the walks that make it out, so they're only rouletted as they
+                // lose energy, which keeps the weights at most one.
+                let survival = if in_medium {
+                    throughput.max_component().min(1.0)
+                } else {
+                    throughput.max_component().min(0.95)
+                };
+                if survival < 1.0 {
+                    if rng.gen_range(0.0..1.0) >= survival {
+                        break;
+                    }
+                    throughput = throughput.div(survival);
+                }
+            }
+        }
+
+        (color, first_hit)
+    }
+}
+
+/// Emission plus a single bounce of light, following specular bounces until a diffuse surface.
+/// Media only attenuate light, without scattering it.
+pub struct DirectLighting;
+
+impl Integrator for DirectLighting {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        depth: u32,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+        background: &Background,
+    ) -> (Color, Option<HitRecord>) {
+        let interval = Interval::new(0.001, f64::INFINITY);
+
+        if depth == 0 {
+            return (Color::black(), None);
+        }
+
+        let Some(object) = world.hit(ray, &interval) else {
+            return (spectral(ray, background.color(ray)), None);
+        };
+        let transmittance = spectral(ray, absorption(ray, &object));
+
+        let color = spectral(ray, object.mat.emitted(&object));
+        let Some(sample) = object.mat.sample(ray, &object) else {
+            return (transmittance * color, Some(object));
+        };
+        let attenuation = spectral(ray, sample.weight);
+        let scattered = sample.ray.with_wavelength(ray.wavelength());
+
+        let Some(pdf) = sample.pdf else {
+            let (incoming, _) = self.ray_color(&scattered, depth - 1, world, lights, background);
+            let color = transmittance * (color + attenuation * incoming);
+            return (color, Some(object));
+        };
+
+        // BSDF sample, counted only where it escapes or lands on an emitter
+        let indirect = match world.hit(&scattered, &interval) {
+            None => spectral(ray, background.color(&scattered)),
+            Some(light) => {
+                let emitted = spectral(ray, light.mat.emitted(&light));
+                let light_pdf = lights.pdf_value(scattered.origin(), scattered.direction());
+                emitted.mul(power_heuristic(pdf, light_pdf))
+            }
+        };
+
+        let direct = sample_light(ray, &object, world, lights);
+        let color = transmittance * (color + direct + attenuation * indirect);
+        (color, Some(object))
+    }
+}
+
+/// Fraction of the hemisphere left unoccluded within `distance` of the first hit
+pub struct AmbientOcclusion {
+    /// Distance in world units within which occluders count
+    pub distance: f64,
+}
+
+impl Integrator for AmbientOcclusion {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        _depth: u32,
+        world: &dyn Hittable,
+        _lights: &dyn Hittable,
+        _background: &Background,
+    ) -> (Color, Option<HitRecord>) {
+        let Some(object) = world.hit(ray, &Interval::new(0.001, f64::INFINITY)) else {
+            return (spectral(ray, Color::white()), None);
+        };
+
+        let direction = Onb::new(&object.normal).local(&vec3::random_cosine_direction());
+
+        let occlusion_ray = Ray::from(object.p, direction);
+        let color = match world.hit(&occlusion_ray, &Interval::new(0.001, self.distance)) {
+            Some(_) => Color::black(),
+            None => spectral(ray, Color::white()),
+        };
+
+        (color, Some(object))
+    }
+}
+
+/// Visualises the world-space normal at the first hit
+pub struct Normals;
+
+impl Integrator for Normals {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        _depth: u32,
+        world: &dyn Hittable,
+        _lights: &dyn Hittable,
+        _background: &Background,
+    ) -> (Color, Option<HitRecord>) {
+        match world.hit(ray, &Interval::new(0.001, f64::INFINITY)) {
+            Some(object) => {
+                let color = spectral(ray, (object.normal + Color::white()).mul(0.5));
+                (color, Some(object))
+            }
+            None => (Color::black(), None),
+        }
+    }
+}
+
+// Beer-Lambert attenuation for a ray that travelled inside a medium, which is the case when it
+// reaches the back face of a surface enclosing one
+fn absorption(ray: &Ray, record: &HitRecord) -> Color {
+    match record.mat.medium() {
+        Some(medium) if !record.front_face => {
+            medium.transmittance(record.t * ray.direction().length())
+        }
+        _ => Color::white(),
+    }
+}
+
+// Random walk step for a ray travelling inside a medium towards the surface at `record`. Returns
+// the ray leaving the point it scattered at, if it did before reaching the surface, along with
+// the throughput weight.
+fn medium_interaction(ray: &Ray, record: &HitRecord) -> (Option<Ray>, Color) {
+    let Some(medium) = record.mat.medium().filter(|_| !record.front_face) else {
+        return (None, Color::white());
+    };
+
+    let length = ray.direction().length();
+    let (scattered, weight) = medium.sample_distance(record.t * length);
+    let next = scattered.map(|distance| {
+        // Isotropic phase function, whose value and density cancel out
+        Ray::from(ray.at(distance / length), vec3::random_unit_vector())
+    });
+
+    (next, weight)
+}
+
+// Next event estimation: connect the hit point to a point sampled on the lights and weight
+// the contribution against BSDF sampling with the power heuristic
+fn sample_light(
+    ray: &Ray,
+    record: &HitRecord,
+    world: &dyn Hittable,
+    lights: &dyn Hittable,
+) -> Color {
+    let direction = lights.random(&record.p);
+    let light_pdf = lights.pdf_value(&record.p, &direction);
+    if light_pdf <= 0.0 {
+        return Color::black();
+    }
+
+    let shadow_ray = Ray::from(record.p, direction).with_wavelength(ray.wavelength());
+    let bsdf_pdf = record.mat.pdf(ray, record, &shadow_ray);
+    if bsdf_pdf <= 0.0 {
+        return Color::black();
+    }
+
+    // The shadow ray is unoccluded when the first surface it hits is the sampled light
+    let interval = Interval::new(0.001, f64::INFINITY);
+    let (Some(light), Some(hit)) = (
+        lights.hit(&shadow_ray, &interval),
+        world.hit(&shadow_ray, &interval),
+    ) else {
+        return Color::black();
+    };
+    if hit.t < light.t - 1e-6 {
+        return Color::black();
+    }
+    let emitted = hit.mat.emitted(&hit);
+    if emitted.is_black() {
+        return Color::black();
+    }
+
+    let bsdf = spectral(ray, record.mat.eval(ray, record, &shadow_ray));
+    let weight = power_heuristic(light_pdf, bsdf_pdf) / light_pdf;
+    (bsdf * spectral(ray, emitted)).mul(weight)
+}
+
+// Value of `color` at the ray's wavelength, replicated across channels, for spectral paths
+fn spectral(ray: &Ray, color: Color) -> Color {
+    match ray.wavelength() {
+        Some(lambda) => {
+            let value = spectrum::upsample(color, lambda);
+            Color::from(value, value, value)
+        }
+        None => color,
+    }
+}
+
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let a = pdf * pdf;
+    let b = other_pdf * other_pdf;
+
+    a / (a + b)
+}
@@ -1,6 +1,12 @@
+//! Ranges of real numbers
+
+/// Range of real numbers from `min` to `max`, empty when `min` is larger. The default covers
+/// everything.
 #[derive(Copy, Clone)]
 pub struct Interval {
+    /// Lower bound
     pub min: f64,
+    /// Upper bound
     pub max: f64,
 }
 
@@ -14,10 +20,17 @@ impl Default for Interval {
 }
 
 impl Interval {
+    /// Interval from `min` to `max`
     pub fn new(min: f64, max: f64) -> Self {
         Interval { min, max }
     }
 
+    /// Whether `x` lies in the closed interval
+    pub fn contains(&self, x: f64) -> bool {
+        self.min <= x && x <= self.max
+    }
+
+    /// Whether `x` lies strictly inside the interval
     pub fn surrounds(&self, x: f64) -> bool {
         self.min < x && x < self.max
     }
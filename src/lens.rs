@@ -0,0 +1,248 @@
+//! Lens systems made of real lens elements
+
+use std::{fs, io, path::Path};
+
+use crate::vec3::{self, Vec3};
+
+// One spherical surface of a lens, or the aperture stop when `radius` is zero. Lengths are in
+// millimetres.
+#[derive(Clone, Copy)]
+struct Surface {
+    // Radius of curvature, positive when the center of curvature is behind the surface
+    radius: f64,
+    // Distance along the axis to the next surface towards the film
+    thickness: f64,
+    // Index of refraction of the medium between this surface and the next
+    ior: f64,
+    aperture_radius: f64,
+}
+
+/// Sequence of spherical lens elements in front of the film, as found in lens design books and
+/// patents. In lens space the film lies in the plane z = 0 and the lens extends towards +z.
+#[derive(Clone)]
+pub struct LensSystem {
+    surfaces: Vec<Surface>,
+    // Position of each surface's vertex along the axis, kept in step with the thicknesses
+    vertices: Vec<f64>,
+}
+
+impl LensSystem {
+    /// Reads a lens prescription with one surface per line, from the front of the lens to the
+    /// back, given as its radius, thickness, index of refraction and aperture diameter. An
+    /// index of zero stands for air and `#` starts a comment.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut surfaces = Vec::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_default();
+            let [radius, thickness, ior, aperture] = values[..] else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected radius, thickness, ior and aperture in \"{line}\""),
+                ));
+            };
+
+            surfaces.push(Surface {
+                radius,
+                thickness,
+                ior: if ior == 0.0 { 1.0 } else { ior },
+                aperture_radius: aperture / 2.0,
+            });
+        }
+
+        if surfaces.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "lens prescription has no surfaces",
+            ));
+        }
+
+        let mut system = Self {
+            surfaces,
+            vertices: Vec::new(),
+        };
+        system.place_vertices();
+
+        // Exposure is measured at the film center, which needs some light to measure with the
+        // lens focused far away
+        let mut focused = system.clone();
+        focused.focus(1e6);
+        if focused.transmission() <= 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no light reaches the center of the film through the lens",
+            ));
+        }
+
+        Ok(system)
+    }
+
+    /// Moves the film so that points `distance` millimetres in front of it are in focus
+    pub fn focus(&mut self, distance: f64) {
+        // Prescriptions often leave the film distance out, so start from a guess
+        let rear = self.surfaces.len() - 1;
+        if self.surfaces[rear].thickness <= 0.0 {
+            self.surfaces[rear].thickness = 50.0;
+            self.place_vertices();
+        }
+
+        // The object's distance from the film changes as the film moves, which settles after
+        // a few rounds
+        for _ in 0..8 {
+            let height = self.surfaces[0].aperture_radius * 0.01;
+            let front = self.vertices[0];
+            let origin = Vec3::from(0.0, 0.0, distance);
+            let target = Vec3::from(height, 0.0, front);
+            let Some((origin, direction)) = self.trace(origin, target - origin, false) else {
+                return;
+            };
+            if direction.x().abs() < 1e-12 {
+                return;
+            }
+
+            // Where the ray crosses the axis again, which is where the image forms
+            let image = origin.z() - origin.x() * direction.z() / direction.x();
+            self.surfaces[rear].thickness -= image;
+            self.place_vertices();
+        }
+    }
+
+    /// Radius of the rear element, which rays from the film are aimed at
+    pub fn rear_aperture(&self) -> f64 {
+        self.surfaces[self.surfaces.len() - 1].aperture_radius
+    }
+
+    /// Distance of the rear element's vertex from the film
+    pub fn rear_vertex(&self) -> f64 {
+        self.vertices[self.surfaces.len() - 1]
+    }
+
+    /// Share of the rays from the center of the film towards the rear element that make it
+    /// through the lens, which brightness across the film is measured against
+    pub fn transmission(&self) -> f64 {
+        let (radius, rear) = (self.rear_aperture(), self.rear_vertex());
+        let steps = 64;
+        let (mut inside, mut passed) = (0, 0);
+        for i in 0..steps {
+            for j in 0..steps {
+                let x = ((i as f64 + 0.5) / steps as f64 * 2.0 - 1.0) * radius;
+                let y = ((j as f64 + 0.5) / steps as f64 * 2.0 - 1.0) * radius;
+                if x * x + y * y > radius * radius {
+                    continue;
+                }
+                inside += 1;
+                let direction = Vec3::from(x, y, rear);
+                if self.trace_from_film(Vec3::new(), direction).is_some() {
+                    passed += 1;
+                }
+            }
+        }
+
+        passed as f64 / inside as f64
+    }
+
+    /// Traces a ray from the film out through the lens, returning it as it leaves the front
+    /// element or `None` when the housing or an aperture blocks it
+    pub fn trace_from_film(&self, origin: Vec3, direction: Vec3) -> Option<(Vec3, Vec3)> {
+        self.trace(origin, direction, true)
+    }
+
+    fn place_vertices(&mut self) {
+        let mut position = 0.0;
+        self.vertices = vec![0.0; self.surfaces.len()];
+        for (i, surface) in self.surfaces.iter().enumerate().rev() {
+            position += surface.thickness;
+            self.vertices[i] = position;
+        }
+    }
+
+    fn trace(&self, origin: Vec3, direction: Vec3, outwards: bool) -> Option<(Vec3, Vec3)> {
+        let count = self.surfaces.len();
+        let (mut origin, mut direction) = (origin, direction.unit());
+        for step in 0..count {
+            let i = if outwards { count - 1 - step } else { step };
+            let surface = &self.surfaces[i];
+            let z = self.vertices[i];
+            let front_ior = if i == 0 {
+                1.0
+            } else {
+                self.surfaces[i - 1].ior
+            };
+            let (ior_in, ior_out) = if outwards {
+                (surface.ior, front_ior)
+            } else {
+                (front_ior, surface.ior)
+            };
+
+            if surface.radius == 0.0 {
+                // The aperture stop is a hole in a plane
+                let t = (z - origin.z()) / direction.z();
+                if t <= 0.0 {
+                    return None;
+                }
+                origin += direction.mul(t);
+                if origin.x().powi(2) + origin.y().powi(2) > surface.aperture_radius.powi(2) {
+                    return None;
+                }
+                continue;
+            }
+
+            let center = Vec3::from(0.0, 0.0, z - surface.radius);
+            let p = intersect_cap(origin, direction, center, surface.radius, z)?;
+            if p.x().powi(2) + p.y().powi(2) > surface.aperture_radius.powi(2) {
+                return None;
+            }
+
+            let mut normal = (p - center).unit();
+            if normal.dot(&direction) > 0.0 {
+                normal = -normal;
+            }
+            let eta = ior_in / ior_out;
+            let cos_theta = -direction.dot(&normal).min(1.0);
+            if eta * (1.0 - cos_theta * cos_theta).sqrt() > 1.0 {
+                return None;
+            }
+
+            origin = p;
+            direction = vec3::refract(&direction, &normal, eta).unit();
+        }
+
+        Some((origin, direction))
+    }
+}
+
+// Intersection of a ray with the part of a sphere nearest the surface's vertex at `vertex`
+fn intersect_cap(
+    origin: Vec3,
+    direction: Vec3,
+    center: Vec3,
+    radius: f64,
+    vertex: f64,
+) -> Option<Vec3> {
+    let oc = origin - center;
+    let half_b = oc.dot(&direction);
+    let c = oc.length_squared() - radius * radius;
+    let discriminant = half_b * half_b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let sqrtd = discriminant.sqrt();
+    [-half_b - sqrtd, -half_b + sqrtd]
+        .into_iter()
+        .filter(|t| *t > 1e-9)
+        .map(|t| origin + direction.mul(t))
+        .min_by(|a, b| {
+            let a = (a.z() - vertex).abs();
+            let b = (b.z() - vertex).abs();
+            a.total_cmp(&b)
+        })
+}
@@ -0,0 +1,56 @@
+//! Physically based path tracer, grown out of *Ray Tracing in One Weekend*.
+//!
+//! A scene is a [`HitList`] of objects, each made of a [`Material`], plus a second list of the
+//! emissive objects to sample as lights. A [`CameraBuilder`] sets up the view and how it's
+//! rendered, and the [`Camera`] it builds renders the scene into a [`film::Film`].
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use weekend_raytracer::{material::Lambertian, CameraBuilder, HitList, Hittable, Sphere, Vec3};
+//!
+//! let ground = Arc::new(Lambertian::new(Vec3::from(0.5, 0.5, 0.5)));
+//! let ball = Arc::new(Lambertian::new(Vec3::from(0.1, 0.2, 0.5)));
+//!
+//! let mut world = HitList::new();
+//! world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+//! world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, ball));
+//!
+//! let mut camera = CameraBuilder::new();
+//! camera.look_from(Vec3::from(0.0, 0.0, 1.0));
+//! camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+//!
+//! let lights: HitList<Box<dyn Hittable>> = HitList::new();
+//! camera.build().render(&world, &lights).save("image.png").unwrap();
+//! ```
+
+pub mod animation;
+pub mod aperture;
+pub mod bump;
+pub mod camera;
+pub mod denoise;
+pub mod film;
+pub mod hit;
+pub mod integrator;
+pub mod interval;
+pub mod lens;
+pub mod mask;
+pub mod material;
+mod microfacet;
+mod onb;
+pub mod principled;
+pub mod quad;
+pub mod ray;
+pub mod sequence;
+mod spectrum;
+pub mod sphere;
+pub mod texture;
+pub mod vec3;
+
+pub use camera::{Camera, CameraBuilder};
+pub use hit::{HitList, HitRecord, Hittable};
+pub use interval::Interval;
+pub use material::Material;
+pub use ray::Ray;
+pub use sphere::Sphere;
+pub use vec3::Vec3;
@@ -1,118 +1,456 @@
-use image::ImageBuffer;
-use rand::{thread_rng, Rng};
-use sphere::Sphere;
-use vec3::{Color, Vec3};
-
-use crate::{camera::CameraBuilder, hit::HitList, material::Material};
-
-mod camera;
-mod hit;
-mod interval;
-mod material;
-mod ray;
-mod sphere;
-mod vec3;
-
-// Sample scene used while implementing
-#[allow(dead_code)]
-fn test_scene() -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
-    // Materials
-    let ground = Material::Lambertian(Color::from(0.8, 0.8, 0.0));
-    let center = Material::Lambertian(Color::from(0.1, 0.2, 0.5));
-    let left = Material::Dielectric(1.5);
-    let right = Material::Metal(Color::from(0.8, 0.6, 0.2), 0.0);
-
-    // World
-    let mut world = HitList::new();
-    world.push(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
-    world.push(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, center));
-    world.push(Sphere::new(Vec3::from(-1.0, 0.0, -1.0), 0.5, left.clone()));
-    world.push(Sphere::new(Vec3::from(-1.0, 0.0, -1.0), -0.4, left));
-    world.push(Sphere::new(Vec3::from(1.0, 0.0, -1.0), 0.5, right));
-
-    // Camera
-    let mut camera = CameraBuilder::new();
-    camera.image_width(800);
-    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
-    camera.look_from(Vec3::from(-2.0, 2.0, 1.0));
-    camera.v_fov(20.0);
-    camera.samples(25);
-    camera.max_depth(10);
-    camera.defocus_angle(10.0);
-    camera.focus_dist(3.4);
-    let camera = camera.build();
+use std::{rc::Rc, sync::Arc};
+
+use scenes::Scene;
+use weekend_raytracer::{
+    aperture::{Aperture, ApertureImage},
+    camera::{CameraBuilder, Crop, Projection, Stereo, StereoLayout},
+    denoise::Denoiser,
+    film::{Aov, Film},
+    hit::Hittable,
+    integrator::{AmbientOcclusion, DirectLighting, Integrator, Normals, PathTracer},
+    lens::LensSystem,
+    sequence,
+};
+
+mod scenes;
 
-    // Render
-    camera.render(&world)
+struct Options {
+    scene: Option<String>,
+    integrator: Option<String>,
+    rr_depth: Option<u32>,
+    aovs: Vec<Aov>,
+    exr: bool,
+    denoise: Option<f64>,
+    spectral: bool,
+    projection: Option<Projection>,
+    stereo: Option<Stereo>,
+    lens: Option<String>,
+    aperture: Option<Aperture>,
+    crop: Option<Crop>,
+    composite: Option<String>,
+    frames: Option<(u32, u32)>,
+    fps: f64,
+    turntable: Option<u32>,
+    animation: Option<String>,
 }
 
-fn cover_art() -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
-    let mut world = HitList::new();
-
-    let ground = Material::Lambertian(Color::from(0.5, 0.5, 0.5));
-    world.push(Sphere::new(Vec3::from(0.0, -1000.0, 0.0), 1000.0, ground));
-
-    let mut rng = thread_rng();
-    let center_offset = Vec3::from(4.0, 0.2, 0.0);
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = rng.gen_range(0.0..1.0);
-            let center = Vec3::from(
-                (a as f64) + 0.9 * rng.gen_range(0.0..1.0),
-                0.2,
-                (b as f64) + 0.9 * rng.gen_range(0.0..1.0),
-            );
-
-            if (center - center_offset).length() > 0.9 {
-                let mat = match choose_mat {
-                    _x if _x < 0.8 => {
-                        let albedo = Color::random_color() * Color::random_color();
-                        Material::Lambertian(albedo)
-                    }
-                    _x if _x < 0.95 => {
-                        let albedo = Color::random_color();
-                        let fuzz = rng.gen_range(0.0..0.5);
-                        Material::Metal(albedo, fuzz)
-                    }
-                    _ => Material::Dielectric(1.5),
-                };
-                world.push(Sphere::new(center, 0.2, mat));
+fn parse_args() -> Options {
+    let mut options = Options {
+        scene: None,
+        integrator: None,
+        rr_depth: None,
+        aovs: Vec::new(),
+        exr: false,
+        denoise: None,
+        spectral: false,
+        projection: None,
+        stereo: None,
+        lens: None,
+        aperture: None,
+        crop: None,
+        composite: None,
+        frames: None,
+        fps: 24.0,
+        turntable: None,
+        animation: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--integrator" => options.integrator = Some(args.next().unwrap_or_else(|| usage())),
+            "--rr-depth" => options.rr_depth = Some(parse_value(args.next())),
+            "--aov" => {
+                let names = args.next().unwrap_or_else(|| usage());
+                for name in names.split(',') {
+                    let aov = match name {
+                        "all" => Aov::ALL.to_vec(),
+                        _ => vec![Aov::from_name(name).unwrap_or_else(|| usage())],
+                    };
+                    options.aovs.extend(aov);
+                }
+            }
+            "--exr" => options.exr = true,
+            "--denoise" => options.denoise = Some(parse_value(args.next())),
+            "--spectral" => options.spectral = true,
+            "--projection" => {
+                let projection = args.next().unwrap_or_else(|| usage());
+                options.projection = Some(parse_projection(&projection));
             }
+            "--stereo" => {
+                let stereo = args.next().unwrap_or_else(|| usage());
+                options.stereo = Some(parse_stereo(&stereo));
+            }
+            "--lens" => options.lens = Some(args.next().unwrap_or_else(|| usage())),
+            "--aperture" => {
+                let aperture = args.next().unwrap_or_else(|| usage());
+                options.aperture = Some(parse_aperture(&aperture));
+            }
+            "--crop" => {
+                let crop = args.next().unwrap_or_else(|| usage());
+                options.crop = Some(parse_crop(&crop));
+            }
+            "--composite" => options.composite = Some(args.next().unwrap_or_else(|| usage())),
+            "--frames" => {
+                let frames = args.next().unwrap_or_else(|| usage());
+                let (first, last) = frames.split_once(':').unwrap_or_else(|| usage());
+                options.frames = Some((
+                    parse_value(Some(first.into())),
+                    parse_value(Some(last.into())),
+                ));
+            }
+            "--fps" => options.fps = parse_value(args.next()),
+            "--turntable" => options.turntable = Some(parse_value(args.next())),
+            "--animation" => match args.next().as_deref() {
+                Some(format @ ("gif" | "apng")) => options.animation = Some(format.to_string()),
+                _ => usage(),
+            },
+            _ if options.scene.is_none() && !arg.starts_with('-') => options.scene = Some(arg),
+            _ => usage(),
         }
     }
 
-    let mat1 = Material::Dielectric(1.5);
-    let mat2 = Material::Lambertian(Color::from(0.4, 0.2, 0.1));
-    let mat3 = Material::Metal(Color::from(0.7, 0.6, 0.5), 0.0);
+    // Composites paste a crop into a previous render, whose AOVs aren't loaded back
+    if options.composite.is_some() && (options.crop.is_none() || !options.aovs.is_empty()) {
+        usage();
+    }
+    // Turntables pick their own frames, and animations need frames to play
+    if options.turntable.is_some() && options.frames.is_some() {
+        usage();
+    }
+    if options.animation.is_some() && options.turntable.is_none() && options.frames.is_none() {
+        usage();
+    }
+    if options.frames.is_some_and(|(first, last)| last < first) {
+        invalid("the last frame comes before the first");
+    }
+    if options.turntable == Some(0) {
+        invalid("turntables need at least one frame");
+    }
+    if !(options.fps.is_finite() && options.fps > 0.0) {
+        invalid("the frame rate must be positive");
+    }
+    if let Some(stereo) = options.stereo {
+        if stereo.convergence <= 0.0 || stereo.interocular < 0.0 {
+            invalid("stereo convergence must be positive and the interocular distance at least 0");
+        }
+        // Other projections have no eyes to converge
+        if !matches!(
+            options.projection,
+            None | Some(Projection::Perspective | Projection::Equirectangular)
+        ) {
+            invalid("stereo needs the perspective or equirectangular projection");
+        }
+    }
 
-    world.push(Sphere::new(Vec3::from(0.0, 1.0, 0.0), 1.0, mat1));
-    world.push(Sphere::new(Vec3::from(-4.0, 1.0, 0.0), 1.0, mat2));
-    world.push(Sphere::new(Vec3::from(4.0, 1.0, 0.0), 1.0, mat3));
+    options
+}
 
-    let mut camera = CameraBuilder::new();
-    camera.image_width(1200);
-    camera.samples(500);
-    camera.max_depth(50);
-    camera.v_fov(20.0);
-    camera.look_from(Vec3::from(13.0, 2.0, 3.0));
-    camera.look_at(Vec3::from(0.0, 0.0, 0.0));
-    camera.v_up(Vec3::from(0.0, 1.0, 0.0));
-    camera.defocus_angle(0.6);
-    camera.focus_dist(10.0);
+// Projections are given by name, followed by their parameter, e.g. `orthographic:4`
+fn parse_projection(arg: &str) -> Projection {
+    let (name, parameter) = match arg.split_once(':') {
+        Some((name, parameter)) => (name, Some(parameter.to_string())),
+        None => (arg, None),
+    };
 
-    let camera = camera.build();
-    camera.render(&world)
+    match name {
+        "perspective" => Projection::Perspective,
+        "equirectangular" => Projection::Equirectangular,
+        "cubemap" => Projection::Cubemap,
+        "fisheye" => Projection::Fisheye {
+            fov: parse_value(parameter),
+        },
+        "orthographic" => Projection::Orthographic {
+            height: parse_value(parameter),
+        },
+        _ => usage(),
+    }
+}
+
+// Stereo is given as the layout, interocular distance and convergence distance, e.g.
+// `sbs:0.1:3`
+fn parse_stereo(arg: &str) -> Stereo {
+    let mut parts = arg.split(':');
+    let layout = match parts.next() {
+        Some("sbs") => StereoLayout::SideBySide,
+        Some("tb") => StereoLayout::TopBottom,
+        _ => usage(),
+    };
+
+    Stereo {
+        interocular: parse_value(parts.next().map(str::to_string)),
+        convergence: parse_value(parts.next().map(str::to_string)),
+        layout,
+    }
+}
+
+// Apertures are `circle`, `blades:COUNT:ROTATION` or the path of a mask image
+fn parse_aperture(arg: &str) -> Aperture {
+    if arg == "circle" {
+        return Aperture::Circle;
+    }
+    if let Some(blades) = arg.strip_prefix("blades:") {
+        let mut parts = blades.split(':');
+        return Aperture::Polygon {
+            blades: parse_value(parts.next().map(str::to_string)),
+            rotation: parts
+                .next()
+                .map_or(0.0, |rotation| parse_value(Some(rotation.into()))),
+        };
+    }
+
+    let image = ApertureImage::load(arg).unwrap_or_else(|err| {
+        eprintln!("error: could not load aperture {arg}: {err}");
+        std::process::exit(1);
+    });
+    Aperture::Image(Arc::new(image))
+}
+
+// Crop windows are given as `X,Y,WIDTH,HEIGHT` in pixels
+fn parse_crop(arg: &str) -> Crop {
+    let values: Vec<u32> = arg
+        .split(',')
+        .map(|value| parse_value(Some(value.into())))
+        .collect();
+    let [x, y, width, height] = values[..] else {
+        usage();
+    };
+    if width == 0 || height == 0 {
+        invalid("crops need a width and height of at least one pixel");
+    }
+
+    Crop {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(arg: Option<String>) -> T {
+    arg.and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn integrator(options: &Options) -> Option<Rc<dyn Integrator>> {
+    let mut path = PathTracer::default();
+    if let Some(rr_depth) = options.rr_depth {
+        path.rr_depth = rr_depth;
+    }
+
+    match options.integrator.as_deref() {
+        None if options.rr_depth.is_none() => None,
+        None | Some("path") => Some(Rc::new(path)),
+        // Only the path tracer terminates paths by Russian roulette
+        Some(_) if options.rr_depth.is_some() => usage(),
+        Some("direct") => Some(Rc::new(DirectLighting)),
+        Some("ao") => Some(Rc::new(AmbientOcclusion { distance: 1.0 })),
+        Some("normals") => Some(Rc::new(Normals)),
+        Some(_) => usage(),
+    }
+}
+
+// Rejects a combination of arguments, saying why before the usage
+fn invalid(message: &str) -> ! {
+    eprintln!("error: {message}");
+    usage()
+}
+
+const USAGE: &str = "usage: weekend-raytracer \
+         [coverart|lights|materials|principled|dispersion|thinfilm|mapping|cutout|brushed|subsurface|bokeh|portrait|animation] \
+         [--integrator path|direct|ao|normals] [--rr-depth N (path only)] \
+         [--aov all|depth,normal,albedo,object,material,frontface] [--exr] \
+         [--denoise STRENGTH] [--spectral] \
+         [--projection perspective|orthographic:HEIGHT|equirectangular|cubemap|fisheye:FOV] \
+         [--stereo sbs|tb:INTEROCULAR:CONVERGENCE] [--lens FILE] \
+         [--aperture circle|blades:COUNT:ROTATION|FILE] \
+         [--crop X,Y,WIDTH,HEIGHT [--composite FILE (no --aov)]] \
+         [--frames FIRST:LAST | --turntable FRAMES] [--fps N] [--animation gif|apng]";
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    std::process::exit(1);
 }
 
 fn main() {
-    let args = std::env::args();
-
-    if args.into_iter().nth(1).is_some_and(|s| s == "coverart") {
-        let image = cover_art();
-        let _ = image.save("cover-art.png");
-    } else {
-        let image = test_scene();
-        let _ = image.save("sample.png");
+    let options = parse_args();
+
+    let (mut scene, output) = match options.scene.as_deref() {
+        Some("coverart") => (scenes::cover_art(), "cover-art"),
+        Some("lights") => (scenes::lights_scene(), "lights"),
+        Some("materials") => (scenes::materials_scene(), "materials"),
+        Some("principled") => (scenes::principled_scene(), "principled"),
+        Some("dispersion") => (scenes::dispersion_scene(), "dispersion"),
+        Some("thinfilm") => (scenes::thin_film_scene(), "thinfilm"),
+        Some("mapping") => (scenes::mapping_scene(), "mapping"),
+        Some("cutout") => (scenes::cutout_scene(), "cutout"),
+        Some("brushed") => (scenes::brushed_scene(), "brushed"),
+        Some("subsurface") => (scenes::subsurface_scene(), "subsurface"),
+        Some("bokeh") => (scenes::bokeh_scene(), "bokeh"),
+        Some("portrait") => (scenes::portrait_scene(), "portrait"),
+        Some("animation") => (scenes::animation_scene(), "animation"),
+        None => (scenes::test_scene(), "sample"),
+        Some(_) => usage(),
+    };
+
+    if let Some(integrator) = integrator(&options) {
+        scene.camera.integrator(integrator);
+    }
+    let mut aovs = options.aovs.clone();
+    if options.denoise.is_some() {
+        // The denoiser is guided by the normal and albedo AOVs
+        for aov in [Aov::Normal, Aov::Albedo] {
+            if !aovs.contains(&aov) {
+                aovs.push(aov);
+            }
+        }
+    }
+    scene.camera.aovs(&aovs);
+    if options.spectral {
+        scene.camera.spectral(true);
+    }
+    if let Some(projection) = options.projection {
+        scene.camera.projection(projection);
+    }
+    if let Some(stereo) = options.stereo {
+        scene.camera.stereo(stereo);
+    }
+    if let Some(path) = &options.lens {
+        let lens = LensSystem::load(path).unwrap_or_else(|err| {
+            eprintln!("error: could not load lens {path}: {err}");
+            std::process::exit(1);
+        });
+        // Diagonal of a 35mm film frame
+        scene.camera.lens_system(lens, 43.27);
+    }
+    if let Some(aperture) = options.aperture.clone() {
+        scene.camera.aperture(aperture);
+    }
+
+    let (width, height) = scene.camera.build().film_size();
+    if let Some(crop) = &options.crop {
+        let (right, bottom) = (
+            crop.x.checked_add(crop.width),
+            crop.y.checked_add(crop.height),
+        );
+        let fits = right.is_some_and(|right| right <= width)
+            && bottom.is_some_and(|bottom| bottom <= height);
+        if !fits {
+            invalid(&format!("crop outside the {width}x{height} image"));
+        }
+    }
+
+    // A crop is written on its own unless it's composited back into a previous render
+    let previous = options.composite.as_ref().map(|path| {
+        let film = Film::load(path).unwrap_or_else(|err| {
+            eprintln!("error: could not load {path}: {err}");
+            std::process::exit(1);
+        });
+        if (film.width(), film.height()) != (width, height) {
+            invalid(&format!(
+                "{path} isn't the size of the {width}x{height} image"
+            ));
+        }
+        film
+    });
+
+    // Turntables orbit the camera a full turn over their frames
+    let frames = match (options.turntable, options.frames) {
+        (Some(count), _) => 0..=count - 1,
+        (None, Some((first, last))) => first..=last,
+        (None, None) => {
+            render(&scene, &scene.camera, &options, previous.as_ref(), output);
+            return;
+        }
+    };
+
+    let mut images = Vec::new();
+    for frame in frames {
+        let time = frame as f64 / options.fps;
+        scene.world.animate(time);
+        scene.lights.animate(time);
+        let mut camera = scene.camera.clone();
+        camera.time(time);
+        if let Some(count) = options.turntable {
+            camera.orbit(360.0 * frame as f64 / count as f64);
+        }
+
+        let output = format!("{output}-{frame:04}");
+        let film = render(&scene, &camera, &options, previous.as_ref(), &output);
+        if options.animation.is_some() {
+            images.push(film.to_image());
+        }
+    }
+
+    let result = match options.animation.as_deref() {
+        Some("gif") => sequence::save_gif(&images, format!("{output}.gif"), options.fps)
+            .map_err(|err| err.to_string()),
+        Some(_) => sequence::save_apng(&images, format!("{output}.apng"), options.fps)
+            .map_err(|err| err.to_string()),
+        None => Ok(()),
+    };
+    if let Err(err) = result {
+        eprintln!("failed to write {output} animation: {err}");
+    }
+}
+
+// Renders the scene as currently posed and saves it as `output`, returning what was saved
+fn render(
+    scene: &Scene,
+    camera: &CameraBuilder,
+    options: &Options,
+    previous: Option<&Film>,
+    output: &str,
+) -> Film {
+    let camera = camera.build();
+    let film = match &options.crop {
+        Some(crop) => camera.render_crop(&scene.world, &scene.lights, crop),
+        None => camera.render_film(&scene.world, &scene.lights),
+    };
+
+    let output = match (&options.crop, previous) {
+        (Some(_), None) => format!("{output}-crop"),
+        _ => output.to_string(),
+    };
+    let composite = |film: &Film| match (&options.crop, previous) {
+        (Some(crop), Some(previous)) => {
+            let mut composite = previous.clone();
+            composite.paste(film, crop.x, crop.y);
+            composite
+        }
+        _ => film.clone(),
+    };
+
+    let saved = composite(&film);
+    save(&saved, &output, &options.aovs, options.exr);
+
+    if let Some(strength) = options.denoise {
+        if let Some(denoised) = Denoiser::new(strength).denoise(&film) {
+            let output = format!("{output}-denoised");
+            save(&composite(&denoised), &output, &[], options.exr);
+        }
+    }
+
+    saved
+}
+
+// Writes the film as an EXR with every AOV as a layer, or as PNGs with one image per AOV listed
+fn save(film: &Film, output: &str, aovs: &[Aov], exr: bool) {
+    if exr {
+        if let Err(err) = film.save_exr(format!("{output}.exr")) {
+            eprintln!("failed to write {output}.exr: {err}");
+        }
+        return;
+    }
+
+    let _ = film.to_image().save(format!("{output}.png"));
+    for aov in aovs {
+        if let Some(image) = film.aov_image(*aov) {
+            let _ = image.save(format!("{output}-{}.png", aov.name()));
+        }
     }
 }
@@ -0,0 +1,83 @@
+//! Alpha masks cutting holes in objects
+
+use std::sync::Arc;
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    hit::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    texture::Texture,
+    vec3::Vec3,
+};
+
+/// How an `AlphaMask` turns opacity into holes
+#[derive(Clone, Copy)]
+pub enum AlphaMode {
+    /// Surfaces are cut out wherever the opacity is below the threshold
+    Threshold(f64),
+    /// Rays pass through with probability one minus the opacity, which blends partially
+    /// transparent surfaces with what's behind them on average
+    Stochastic,
+}
+
+/// Cuts holes in an object, like the gaps between leaves or the wires of a fence, by ignoring
+/// hits where an opacity texture says the surface isn't there. Opacity from an image's alpha
+/// channel is loaded with `Texture::image_alpha`.
+pub struct AlphaMask<T: Hittable> {
+    object: T,
+    alpha: Texture,
+    mode: AlphaMode,
+}
+
+impl<T: Hittable> AlphaMask<T> {
+    /// Masks `object` by the first channel of the `alpha` texture
+    pub fn new(object: T, alpha: Texture, mode: AlphaMode) -> Self {
+        Self {
+            object,
+            alpha,
+            mode,
+        }
+    }
+
+    fn opaque(&self, record: &HitRecord) -> bool {
+        let alpha = self.alpha.value(record.u, record.v, &record.p)[0];
+        match self.mode {
+            AlphaMode::Threshold(threshold) => alpha >= threshold,
+            AlphaMode::Stochastic => alpha > thread_rng().gen_range(0.0..1.0),
+        }
+    }
+}
+
+impl<T: Hittable> Hittable for AlphaMask<T> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let mut ray_t = ray_t.to_owned();
+
+        // Keep looking past cut out hits for one further along the ray
+        loop {
+            let record = self.object.hit(ray, &ray_t)?;
+            if self.opaque(&record) {
+                return Some(record);
+            }
+            ray_t.min = record.t + 1e-6;
+        }
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        self.object.random(origin)
+    }
+
+    fn animate(&mut self, time: f64) {
+        self.object.animate(time);
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        self.object.materials(materials);
+    }
+}
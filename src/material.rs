@@ -1,59 +1,663 @@
+//! How surfaces and media scatter light
+
 use crate::{
     hit::HitRecord,
+    microfacet::{DielectricLobe, Fresnel, Ggx, ReflectionLobe},
+    onb::Onb,
     ray::Ray,
-    vec3::{self, Color},
+    spectrum,
+    texture::Texture,
+    vec3::{self, Color, Vec3},
 };
 use rand::{thread_rng, Rng};
+use std::f64::consts::PI;
 
-#[derive(Clone)]
-pub enum Material {
-    Lambertian(Color),
-    Metal(Color, f64),
-    Dielectric(f64),
+/// Direction picked by `Material::sample`
+pub struct BsdfSample {
+    /// Scattered ray, leaving the hit point in the sampled direction
+    pub ray: Ray,
+    /// BSDF times the cosine term over the density the direction was picked with
+    pub weight: Color,
+    /// Density of the sampled direction, `None` for materials that scatter along a single
+    /// direction, which can't be combined with light sampling
+    pub pdf: Option<f64>,
 }
 
-impl Material {
-    pub fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<(Color, Ray)> {
-        match self {
-            Self::Lambertian(albedo) => {
-                let mut direction = record.normal + vec3::random_unit_vector();
-                if direction.near_zero() {
-                    direction = record.normal;
-                }
+/// Participating medium filling the inside of a closed surface. Light travelling through is
+/// absorbed with density `sigma_a` and scattered in a random direction with density `sigma_s`.
+#[derive(Clone, Copy)]
+pub struct Medium {
+    /// Absorption coefficient per channel, per world unit
+    pub sigma_a: Color,
+    /// Scattering coefficient per channel, per world unit
+    pub sigma_s: Color,
+}
+
+impl Medium {
+    /// Absorption that leaves `color` of the light after travelling `distance`
+    pub fn from_transmittance(color: Color, distance: f64) -> Self {
+        let mut sigma_a = Color::black();
+        for channel in 0..3 {
+            sigma_a[channel] = -color[channel].max(1e-6).ln() / distance;
+        }
+
+        Self {
+            sigma_a,
+            sigma_s: Color::black(),
+        }
+    }
 
-                let scattered = Ray::from(record.p, direction);
+    /// Scattering medium whose light, after bouncing around inside, comes out roughly `albedo`
+    /// colored. The single scattering albedo is found with the fit from Chiang et al.,
+    /// "Practical and Controllable Subsurface Scattering for Production Path Tracing".
+    pub fn from_albedo(albedo: Color, mean_free_path: Color) -> Self {
+        let mut sigma_a = Color::black();
+        let mut sigma_s = Color::black();
+        for channel in 0..3 {
+            let a = albedo[channel].clamp(0.0, 1.0);
+            let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+            let single_scattering = 1.0 - s * s;
 
-                Some((*albedo, scattered))
-            }
-            Self::Metal(albedo, fuzz) => {
-                let reflected = vec3::reflect(&r_in.direction().unit(), &record.normal);
-                let scattered =
-                    Ray::from(record.p, reflected + vec3::random_unit_vector().mul(*fuzz));
+            let sigma_t = 1.0 / mean_free_path[channel].max(1e-6);
+            sigma_s[channel] = single_scattering * sigma_t;
+            sigma_a[channel] = sigma_t - sigma_s[channel];
+        }
+
+        Self { sigma_a, sigma_s }
+    }
+
+    /// Share of light per channel left after travelling `distance` without scattering
+    pub fn transmittance(&self, distance: f64) -> Color {
+        let sigma_t = self.sigma_a + self.sigma_s;
+        Color::from(
+            (-sigma_t[0] * distance).exp(),
+            (-sigma_t[1] * distance).exp(),
+            (-sigma_t[2] * distance).exp(),
+        )
+    }
+
+    /// Samples how far light travels before scattering, given that it would leave the medium
+    /// after `distance`. Returns where it scattered, if it did, and the throughput weight.
+    pub fn sample_distance(&self, distance: f64) -> (Option<f64>, Color) {
+        if self.sigma_s.is_black() {
+            return (None, self.transmittance(distance));
+        }
+
+        // Distances are sampled by one channel's density picked at random, weighted by the
+        // average density of all of them
+        let sigma_t = self.sigma_a + self.sigma_s;
+        let mut rng = thread_rng();
+        let channel = rng.gen_range(0..3);
+        let sampled = -(1.0 - rng.gen_range(0.0..1.0f64)).ln() / sigma_t[channel];
+
+        let scattered = sampled < distance;
+        let distance = distance.min(sampled);
+        let transmittance = self.transmittance(distance);
+        let density = if scattered {
+            sigma_t * transmittance
+        } else {
+            transmittance
+        };
+        let pdf = (density[0] + density[1] + density[2]) / 3.0;
+        if pdf <= 0.0 {
+            return (None, Color::black());
+        }
+
+        if scattered {
+            (Some(distance), (self.sigma_s * transmittance).div(pdf))
+        } else {
+            (None, transmittance.div(pdf))
+        }
+    }
+}
+
+/// Wavelength dependent index of refraction, with wavelengths in micrometres as glass
+/// catalogues quote the coefficients
+#[derive(Clone, Copy)]
+pub enum Dispersion {
+    /// n = a + b / λ²
+    Cauchy {
+        /// Index of refraction at long wavelengths
+        a: f64,
+        /// Rise of the index towards short wavelengths, in μm²
+        b: f64,
+    },
+    /// n² = 1 + Σ b λ² / (λ² - c)
+    Sellmeier {
+        /// Strength of each absorption resonance
+        b: [f64; 3],
+        /// Square of each resonance's wavelength, in μm²
+        c: [f64; 3],
+    },
+}
 
-                Some((*albedo, scattered))
+impl Dispersion {
+    /// Schott N-BK7 crown glass
+    pub const BK7: Self = Self::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    /// Schott SF11 dense flint glass
+    pub const SF11: Self = Self::Sellmeier {
+        b: [1.73759695, 0.313747346, 1.89878101],
+        c: [0.013188707, 0.0623068142, 155.23629],
+    };
+    /// Diamond
+    pub const DIAMOND: Self = Self::Sellmeier {
+        b: [4.3356, 0.3306, 0.0],
+        c: [0.011236, 0.030625, 0.0],
+    };
+
+    /// Index of refraction at `lambda` nanometres
+    pub fn ior(&self, lambda: f64) -> f64 {
+        let lambda = lambda / 1000.0;
+        let lambda2 = lambda * lambda;
+        match self {
+            Self::Cauchy { a, b } => a + b / lambda2,
+            Self::Sellmeier { b, c } => {
+                let sum: f64 = (0..3).map(|i| b[i] * lambda2 / (lambda2 - c[i])).sum();
+                (1.0 + sum).sqrt()
             }
-            Self::Dielectric(ir) => {
-                let refraction_ratio = if record.front_face { 1.0 / *ir } else { *ir };
+        }
+    }
+}
+
+/// Coating a few hundred nanometres thick, like a soap film or an anodised layer, whose
+/// reflections interfere with those off the surface beneath
+#[derive(Clone)]
+pub struct ThinFilm {
+    /// In nanometres
+    pub thickness: Texture,
+    /// Index of refraction of the film
+    pub ior: f64,
+}
+
+impl ThinFilm {
+    fn fresnel(&self, r_in: &Ray, record: &HitRecord, eta: Color, k: Color) -> Fresnel {
+        Fresnel::ThinFilm {
+            eta,
+            k,
+            film_ior: self.ior,
+            thickness: self.thickness.value(record.u, record.v, &record.p)[0],
+            wavelength: r_in.wavelength(),
+        }
+    }
+}
+
+// Index of refraction seen by `ray`, which only depends on its wavelength for dispersive
+// materials traced spectrally
+fn ior_at(ir: f64, dispersion: Option<Dispersion>, ray: &Ray) -> f64 {
+    match (dispersion, ray.wavelength()) {
+        (Some(dispersion), Some(lambda)) => dispersion.ior(lambda),
+        _ => ir,
+    }
+}
+
+/// How light scatters off or is emitted by a surface
+///
+/// `eval` and `pdf` are only consulted for materials whose samples have a pdf, perfectly
+/// specular materials can leave them as the defaults.
+pub trait Material {
+    /// Picks a direction for light scattered from `r_in`, or `None` if it's absorbed
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample>;
+
+    /// BSDF times the cosine term for light arriving along `scattered`
+    fn eval(&self, _r_in: &Ray, _record: &HitRecord, _scattered: &Ray) -> Color {
+        Color::black()
+    }
+
+    /// Density `sample` would pick `scattered` with
+    fn pdf(&self, _r_in: &Ray, _record: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    /// Light given off by the surface
+    fn emitted(&self, _record: &HitRecord) -> Color {
+        Color::black()
+    }
+
+    /// Reflectance of the surface, used as a guide for compositing and denoising
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        Color::white()
+    }
+
+    /// Medium enclosed by surfaces of this material, applied to rays travelling from a front
+    /// face to the back face they hit next
+    fn medium(&self) -> Option<Medium> {
+        None
+    }
+}
+
+/// Ideal diffuse surface, scattering light evenly in all directions
+pub struct Lambertian {
+    albedo: Color,
+}
+
+impl Lambertian {
+    /// Surface reflecting `albedo` of the light falling on it
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Lambertian {
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample> {
+        let mut direction = record.normal + vec3::random_unit_vector();
+        if direction.near_zero() {
+            direction = record.normal;
+        }
+
+        let ray = Ray::from(record.p, direction);
+
+        Some(BsdfSample {
+            pdf: Some(self.pdf(r_in, record, &ray)),
+            ray,
+            weight: self.albedo,
+        })
+    }
+
+    fn eval(&self, _r_in: &Ray, record: &HitRecord, scattered: &Ray) -> Color {
+        let cosine = record.normal.dot(&scattered.direction().unit());
+        self.albedo.mul((cosine / PI).max(0.0))
+    }
+
+    fn pdf(&self, _r_in: &Ray, record: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = record.normal.dot(&scattered.direction().unit());
+        (cosine / PI).max(0.0)
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.albedo
+    }
+}
+
+/// Mirror whose reflections are blurred by perturbing them randomly
+pub struct Metal {
+    albedo: Color,
+    fuzz: f64,
+}
+
+impl Metal {
+    /// Metal tinted `albedo`, with reflections perturbed by up to `fuzz` times their length
+    pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self { albedo, fuzz }
+    }
+}
+
+impl Material for Metal {
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample> {
+        let reflected = vec3::reflect(&r_in.direction().unit(), &record.normal);
+        let ray = Ray::from(
+            record.p,
+            reflected + vec3::random_unit_vector().mul(self.fuzz),
+        );
+
+        Some(BsdfSample {
+            ray,
+            weight: self.albedo,
+            pdf: None,
+        })
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.albedo
+    }
+}
+
+/// Smooth glass, water or other clear material that reflects and refracts light
+pub struct Dielectric {
+    ir: f64,
+    dispersion: Option<Dispersion>,
+    film: Option<ThinFilm>,
+    medium: Option<Medium>,
+}
 
-                let unit_direction = r_in.direction().unit();
+impl Dielectric {
+    /// Clear material with index of refraction `ir`
+    pub fn new(ir: f64) -> Self {
+        Self {
+            ir,
+            dispersion: None,
+            film: None,
+            medium: None,
+        }
+    }
+
+    /// Iridescent coating on the outside of the surface
+    pub fn with_thin_film(self, film: ThinFilm) -> Self {
+        Self {
+            film: Some(film),
+            ..self
+        }
+    }
+
+    /// Splits light into its colors when rendering spectrally, RGB renders use the index at the
+    /// sodium D line
+    pub fn with_dispersion(self, dispersion: Dispersion) -> Self {
+        Self {
+            ir: dispersion.ior(spectrum::LAMBDA_D),
+            dispersion: Some(dispersion),
+            ..self
+        }
+    }
+
+    /// Colored glass or liquid, tinted according to how far light travels through it, or a
+    /// scattering medium
+    pub fn with_absorption(self, medium: Medium) -> Self {
+        Self {
+            medium: Some(medium),
+            ..self
+        }
+    }
+}
 
-                let cos_theta = -unit_direction.dot(&record.normal).min(1.0);
-                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
-                let cannot_refract = refraction_ratio * sin_theta > 1.0;
+impl Material for Dielectric {
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample> {
+        let ir = ior_at(self.ir, self.dispersion, r_in);
+        let refraction_ratio = if record.front_face { 1.0 / ir } else { ir };
+
+        let unit_direction = r_in.direction().unit();
+
+        let cos_theta = -unit_direction.dot(&record.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        let reflected = vec3::reflect(&unit_direction, &record.normal);
+        let refracted = || vec3::refract(&unit_direction, &record.normal, refraction_ratio);
+
+        let (direction, weight) = match &self.film {
+            // The film tints reflection and transmission differently, so rather than choosing
+            // with probability F the choice is made on average and the weights make up for it
+            Some(film) if record.front_face && !cannot_refract => {
+                let substrate = Color::from(ir, ir, ir);
+                let fresnel = film.fresnel(r_in, record, substrate, Color::black());
+                let reflectance = fresnel.reflectance(cos_theta);
+                let probability = (reflectance[0] + reflectance[1] + reflectance[2]) / 3.0;
+                if probability > thread_rng().gen_range(0.0..1.0) {
+                    (reflected, reflectance.div(probability))
+                } else {
+                    let transmittance = Color::white() - reflectance;
+                    (refracted(), transmittance.div(1.0 - probability))
+                }
+            }
+            _ => {
                 let direction = if cannot_refract
                     || reflectance(cos_theta, refraction_ratio) > thread_rng().gen_range(0.0..1.0)
                 {
-                    vec3::reflect(&unit_direction, &record.normal)
+                    reflected
                 } else {
-                    vec3::refract(&unit_direction, &record.normal, refraction_ratio)
+                    refracted()
                 };
+                (direction, Color::white())
+            }
+        };
 
-                let scattered = Ray::from(record.p, direction);
+        Some(BsdfSample {
+            ray: Ray::from(record.p, direction),
+            weight,
+            pdf: None,
+        })
+    }
 
-                Some((Color::white(), scattered))
-            }
+    fn medium(&self) -> Option<Medium> {
+        self.medium
+    }
+}
+
+/// Translucent material like skin, wax or marble, where light refracts into the object and
+/// takes a random walk through the scattering medium inside before leaving again
+pub struct Subsurface {
+    albedo: Color,
+    boundary: Dielectric,
+}
+
+impl Subsurface {
+    /// `mean_free_path` is the average distance light travels inside between scattering events,
+    /// with longer paths giving a softer and more translucent look
+    pub fn new(albedo: Color, mean_free_path: Color, ir: f64) -> Self {
+        Self {
+            albedo,
+            boundary: Dielectric::new(ir)
+                .with_absorption(Medium::from_albedo(albedo, mean_free_path)),
+        }
+    }
+}
+
+impl Material for Subsurface {
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample> {
+        self.boundary.sample(r_in, record)
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.boundary.medium()
+    }
+}
+
+/// Light source emitting evenly from the front of its surface, which reflects nothing
+pub struct DiffuseLight {
+    emit: Color,
+}
+
+impl DiffuseLight {
+    /// Light with radiance `emit`
+    pub fn new(emit: Color) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn sample(&self, _r_in: &Ray, _record: &HitRecord) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, record: &HitRecord) -> Color {
+        if record.front_face {
+            self.emit
+        } else {
+            Color::black()
+        }
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        let mut emit = self.emit;
+        for channel in emit.iter_mut() {
+            *channel = channel.clamp(0.0, 1.0);
+        }
+        emit
+    }
+}
+
+/// GGX microfacet metal with a complex index of refraction `eta + ik` per channel
+pub struct RoughConductor {
+    eta: Color,
+    k: Color,
+    distribution: Ggx,
+    flow: Option<Texture>,
+    film: Option<ThinFilm>,
+}
+
+impl RoughConductor {
+    /// Metal with the same `roughness` in every direction
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self::anisotropic(eta, k, roughness, roughness)
+    }
+
+    /// Brushed metal, with the roughness along the surface tangent and across it given
+    /// separately. The tangent follows the primitive's u direction unless a flow map is set.
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: Ggx::new(roughness_u, roughness_v),
+            flow: None,
+            film: None,
+        }
+    }
+
+    /// Direction of the brushing as a tangent space vector, with red and green mapping [0, 1]
+    /// to [-1, 1] along the primitive's tangent and bitangent
+    pub fn with_flow_map(self, flow: Texture) -> Self {
+        Self {
+            flow: Some(flow),
+            ..self
         }
     }
+
+    /// Oxide or coating layer, like anodised titanium or heat tinted steel
+    pub fn with_thin_film(self, film: ThinFilm) -> Self {
+        Self {
+            film: Some(film),
+            ..self
+        }
+    }
+
+    // Shading frame with the x axis along the direction the roughness is `roughness_u` in
+    fn frame(&self, record: &HitRecord) -> Onb {
+        let mut tangent = record.tangent;
+        if let Some(flow) = &self.flow {
+            let base = Onb::from_tangent(&record.normal, &record.tangent);
+            let direction = flow.value(record.u, record.v, &record.p);
+            tangent =
+                base.u().mul(2.0 * direction[0] - 1.0) + base.v().mul(2.0 * direction[1] - 1.0);
+        }
+
+        Onb::from_tangent(&record.normal, &tangent)
+    }
+
+    fn lobe(&self, r_in: &Ray, record: &HitRecord) -> ReflectionLobe {
+        let fresnel = match &self.film {
+            Some(film) => film.fresnel(r_in, record, self.eta, self.k),
+            None => Fresnel::Conductor {
+                eta: self.eta,
+                k: self.k,
+            },
+        };
+
+        ReflectionLobe {
+            distribution: self.distribution,
+            fresnel,
+        }
+    }
+}
+
+impl Material for RoughConductor {
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample> {
+        let frame = self.frame(record);
+        let wo = frame.to_local(&-r_in.direction().unit());
+        let lobe = self.lobe(r_in, record);
+        let (weight, wi) = lobe.sample(&wo)?;
+
+        Some(BsdfSample {
+            ray: Ray::from(record.p, frame.local(&wi)),
+            weight,
+            pdf: Some(lobe.pdf(&wo, &wi)),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, record: &HitRecord, scattered: &Ray) -> Color {
+        let frame = self.frame(record);
+        let wo = frame.to_local(&-r_in.direction().unit());
+        let wi = frame.to_local(&scattered.direction().unit());
+        self.lobe(r_in, record).eval(&wo, &wi)
+    }
+
+    fn pdf(&self, r_in: &Ray, record: &HitRecord, scattered: &Ray) -> f64 {
+        let frame = self.frame(record);
+        let wo = frame.to_local(&-r_in.direction().unit());
+        let wi = frame.to_local(&scattered.direction().unit());
+        self.lobe(r_in, record).pdf(&wo, &wi)
+    }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        let r_in = Ray::from(record.p, -record.normal);
+        self.lobe(&r_in, record).fresnel.reflectance(1.0)
+    }
+}
+
+/// GGX microfacet glass
+pub struct RoughDielectric {
+    ir: f64,
+    dispersion: Option<Dispersion>,
+    distribution: Ggx,
+    medium: Option<Medium>,
+}
+
+impl RoughDielectric {
+    /// Frosted material with index of refraction `ir` and the given `roughness`
+    pub fn new(ir: f64, roughness: f64) -> Self {
+        Self {
+            ir,
+            dispersion: None,
+            distribution: Ggx::isotropic(roughness),
+            medium: None,
+        }
+    }
+
+    /// Splits light into its colors when rendering spectrally, see `Dielectric::with_dispersion`
+    pub fn with_dispersion(self, dispersion: Dispersion) -> Self {
+        Self {
+            ir: dispersion.ior(spectrum::LAMBDA_D),
+            dispersion: Some(dispersion),
+            ..self
+        }
+    }
+
+    /// Medium inside the material, see `Dielectric::with_absorption`
+    pub fn with_absorption(self, medium: Medium) -> Self {
+        Self {
+            medium: Some(medium),
+            ..self
+        }
+    }
+
+    fn lobe(&self, r_in: &Ray, record: &HitRecord) -> DielectricLobe {
+        let ir = ior_at(self.ir, self.dispersion, r_in);
+        DielectricLobe {
+            distribution: self.distribution,
+            eta: if record.front_face { ir } else { 1.0 / ir },
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample> {
+        let frame = Onb::new(&record.normal);
+        let wo = frame.to_local(&-r_in.direction().unit());
+        let lobe = self.lobe(r_in, record);
+        let (weight, wi) = lobe.sample(&wo)?;
+
+        Some(BsdfSample {
+            ray: Ray::from(record.p, frame.local(&wi)),
+            weight,
+            pdf: Some(lobe.pdf(&wo, &wi)),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, record: &HitRecord, scattered: &Ray) -> Color {
+        let (wo, wi) = local_directions(r_in, record, scattered);
+        self.lobe(r_in, record).eval(&wo, &wi)
+    }
+
+    fn pdf(&self, r_in: &Ray, record: &HitRecord, scattered: &Ray) -> f64 {
+        let (wo, wi) = local_directions(r_in, record, scattered);
+        self.lobe(r_in, record).pdf(&wo, &wi)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.medium
+    }
+}
+
+/// Outgoing and incident directions in the shading frame around the hit normal
+pub fn local_directions(r_in: &Ray, record: &HitRecord, scattered: &Ray) -> (Vec3, Vec3) {
+    let frame = Onb::new(&record.normal);
+    let wo = frame.to_local(&-r_in.direction().unit());
+    let wi = frame.to_local(&scattered.direction().unit());
+
+    (wo, wi)
 }
 
 fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
@@ -0,0 +1,483 @@
+use std::{f64::consts::PI, ops};
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    spectrum,
+    vec3::{self, Color, Vec3},
+};
+
+/// Trowbridge-Reitz (GGX) microfacet distribution. Directions are in the local shading frame,
+/// where the macrosurface normal is +z.
+#[derive(Clone, Copy)]
+pub struct Ggx {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl Ggx {
+    /// Perceptual roughness is squared to get alpha, which is clamped to avoid the singular
+    /// perfectly smooth case
+    pub fn new(roughness_x: f64, roughness_y: f64) -> Self {
+        Self {
+            alpha_x: (roughness_x * roughness_x).max(1e-4),
+            alpha_y: (roughness_y * roughness_y).max(1e-4),
+        }
+    }
+
+    pub fn isotropic(roughness: f64) -> Self {
+        Self::new(roughness, roughness)
+    }
+
+    pub fn d(&self, wm: &Vec3) -> f64 {
+        let cos2_theta = wm.z() * wm.z();
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+
+        let x = wm.x() / self.alpha_x;
+        let y = wm.y() / self.alpha_y;
+        let e = x * x + y * y + cos2_theta;
+
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2_theta = w.z() * w.z();
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+
+        let x = w.x() * self.alpha_x;
+        let y = w.y() * self.alpha_y;
+        let tan2_alpha2 = (x * x + y * y) / cos2_theta;
+
+        ((1.0 + tan2_alpha2).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated Smith shadowing-masking
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of microfacet normals visible from `w`
+    pub fn visible_d(&self, w: &Vec3, wm: &Vec3) -> f64 {
+        let cos_theta = w.z().abs();
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+
+        self.g1(w) / cos_theta * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// Samples a normal from the distribution of visible normals (Heitz 2018)
+    pub fn sample_visible(&self, w: &Vec3) -> Vec3 {
+        let mut rng = thread_rng();
+        let u1: f64 = rng.gen_range(0.0..1.0);
+        let u2: f64 = rng.gen_range(0.0..1.0);
+
+        // Transform to the hemisphere configuration
+        let wh = Vec3::from(self.alpha_x * w.x(), self.alpha_y * w.y(), w.z()).unit();
+
+        // Orthonormal basis around the view direction
+        let len2 = wh.x() * wh.x() + wh.y() * wh.y();
+        let t1 = if len2 > 0.0 {
+            Vec3::from(-wh.y(), wh.x(), 0.0).div(len2.sqrt())
+        } else {
+            Vec3::from(1.0, 0.0, 0.0)
+        };
+        let t2 = wh.cross(&t1);
+
+        // Sample the projected area of the visible hemisphere
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let p2 = r * phi.sin();
+        let s = 0.5 * (1.0 + wh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * p2;
+        let p3 = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let nh = t1.mul(p1) + t2.mul(p2) + wh.mul(p3);
+
+        // Transform back to the ellipsoid configuration
+        Vec3::from(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit()
+    }
+}
+
+/// Unpolarised Fresnel reflectance at a boundary with relative index of refraction `eta`
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(-1.0, 1.0);
+    let (cos_theta_i, eta) = if cos_theta_i < 0.0 {
+        (-cos_theta_i, 1.0 / eta)
+    } else {
+        (cos_theta_i, eta)
+    };
+
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction `eta + ik` per channel
+pub fn fresnel_conductor(cos_theta_i: f64, eta: &Color, k: &Color) -> Color {
+    let mut reflectance = Color::black();
+    for channel in 0..3 {
+        reflectance[channel] = fresnel_complex(cos_theta_i, eta[channel], k[channel]);
+    }
+
+    reflectance
+}
+
+fn fresnel_complex(cos_theta_i: f64, eta: f64, k: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let cos2_theta_i = cos_theta_i * cos_theta_i;
+    let sin2_theta_i = 1.0 - cos2_theta_i;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2_theta_i;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let t1 = a2_plus_b2 + cos2_theta_i;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_theta_i * a;
+    let r_s = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2_theta_i * a2_plus_b2 + sin2_theta_i * sin2_theta_i;
+    let t4 = t2 * sin2_theta_i;
+    let r_p = r_s * (t3 - t4) / (t3 + t4);
+
+    (r_p + r_s) / 2.0
+}
+
+/// Reflectance of a substrate with complex index of refraction `eta + ik` under a film
+/// `thickness` nanometres thick, summing the light bouncing back and forth inside the film with
+/// Airy's formula so that its reflections interfere at wavelength `lambda`
+pub fn fresnel_thin_film(
+    cos_theta_i: f64,
+    film_ior: f64,
+    thickness: f64,
+    eta: f64,
+    k: f64,
+    lambda: f64,
+) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+
+    let n0 = Complex::real(1.0);
+    let n1 = Complex::real(film_ior);
+    let n2 = Complex::new(eta, k);
+    let cos0 = Complex::real(cos_theta_i);
+    let cos1 = (Complex::real(1.0) - Complex::real(sin2_theta_i) / (n1 * n1)).sqrt();
+    let cos2 = (Complex::real(1.0) - Complex::real(sin2_theta_i) / (n2 * n2)).sqrt();
+
+    // Round trip through the film
+    let phase = Complex::real(4.0 * PI * thickness / lambda) * n1 * cos1;
+    let delay = (Complex::new(0.0, 1.0) * phase).exp();
+
+    let airy = |r01: Complex, r12: Complex| {
+        let r = (r01 + r12 * delay) / (Complex::real(1.0) + r01 * r12 * delay);
+        r.norm_sqr()
+    };
+    let r_s = airy(
+        (n0 * cos0 - n1 * cos1) / (n0 * cos0 + n1 * cos1),
+        (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2),
+    );
+    let r_p = airy(
+        (n1 * cos0 - n0 * cos1) / (n1 * cos0 + n0 * cos1),
+        (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2),
+    );
+
+    ((r_s + r_p) / 2.0).clamp(0.0, 1.0)
+}
+
+#[derive(Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn real(re: f64) -> Self {
+        Self { re, im: 0.0 }
+    }
+
+    fn norm_sqr(&self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    // Principal square root
+    fn sqrt(&self) -> Self {
+        let norm = self.norm_sqr().sqrt();
+        let re = ((norm + self.re) / 2.0).max(0.0).sqrt();
+        let im = ((norm - self.re) / 2.0).max(0.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    fn exp(&self) -> Self {
+        let scale = self.re.exp();
+        Self::new(scale * self.im.cos(), scale * self.im.sin())
+    }
+}
+
+impl ops::Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl ops::Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl ops::Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl ops::Div for Complex {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let norm = rhs.norm_sqr();
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / norm,
+            (self.im * rhs.re - self.re * rhs.im) / norm,
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Fresnel {
+    /// Complex index of refraction `eta + ik` per channel
+    Conductor { eta: Color, k: Color },
+    /// Schlick's approximation from the reflectance at normal incidence
+    Schlick(Color),
+    /// Conductor or dielectric substrate under a thin film, evaluated at the path's wavelength
+    /// or at a representative wavelength per channel
+    ThinFilm {
+        eta: Color,
+        k: Color,
+        film_ior: f64,
+        thickness: f64,
+        wavelength: Option<f64>,
+    },
+}
+
+impl Fresnel {
+    pub fn reflectance(&self, cos_theta_i: f64) -> Color {
+        match self {
+            Self::Conductor { eta, k } => fresnel_conductor(cos_theta_i, eta, k),
+            Self::Schlick(f0) => {
+                let weight = (1.0 - cos_theta_i.clamp(0.0, 1.0)).powi(5);
+                *f0 + (Color::white() - *f0).mul(weight)
+            }
+            Self::ThinFilm {
+                eta,
+                k,
+                film_ior,
+                thickness,
+                wavelength,
+            } => {
+                let reflectance = |eta, k, lambda| {
+                    fresnel_thin_film(cos_theta_i, *film_ior, *thickness, eta, k, lambda)
+                };
+                match wavelength {
+                    Some(lambda) => {
+                        let eta = spectrum::upsample(*eta, *lambda);
+                        let k = spectrum::upsample(*k, *lambda);
+                        let value = reflectance(eta, k, *lambda);
+                        Color::from(value, value, value)
+                    }
+                    None => {
+                        let mut color = Color::black();
+                        for channel in 0..3 {
+                            let lambda = spectrum::RGB_WAVELENGTHS[channel];
+                            color[channel] = reflectance(eta[channel], k[channel], lambda);
+                        }
+                        color
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Rough reflection off the microfacets, with no light entering the surface
+#[derive(Clone, Copy)]
+pub struct ReflectionLobe {
+    pub distribution: Ggx,
+    pub fresnel: Fresnel,
+}
+
+impl ReflectionLobe {
+    /// Returns the sampled direction and its weight, the BSDF times the cosine over the pdf
+    pub fn sample(&self, wo: &Vec3) -> Option<(Color, Vec3)> {
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let wm = self.distribution.sample_visible(wo);
+        let wi = wm.mul(2.0 * wo.dot(&wm)) - *wo;
+        if wi.z() <= 0.0 {
+            return None;
+        }
+
+        // Visible normal sampling leaves only the masking of the outgoing direction
+        let fresnel = self.fresnel.reflectance(wo.dot(&wm));
+        let shadowing = self.distribution.g(wo, &wi) / self.distribution.g1(wo);
+
+        Some((fresnel.mul(shadowing), wi))
+    }
+
+    /// BSDF times the cosine of the incident direction
+    pub fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::black();
+        }
+
+        let wm = (*wo + *wi).unit();
+        let fresnel = self.fresnel.reflectance(wo.dot(&wm));
+        let d = self.distribution.d(&wm);
+        let g = self.distribution.g(wo, wi);
+
+        fresnel.mul(d * g / (4.0 * wo.z()))
+    }
+
+    pub fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wm = (*wo + *wi).unit();
+        self.distribution.visible_d(wo, &wm) / (4.0 * wo.dot(&wm).abs())
+    }
+}
+
+/// Rough glass lobe, reflecting or refracting through the microfacets. `eta` is the index of
+/// refraction on the far side of the surface relative to the side `wo` is on.
+#[derive(Clone, Copy)]
+pub struct DielectricLobe {
+    pub distribution: Ggx,
+    pub eta: f64,
+}
+
+impl DielectricLobe {
+    pub fn sample(&self, wo: &Vec3) -> Option<(Color, Vec3)> {
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let wm = self.distribution.sample_visible(wo);
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.eta);
+
+        let wi = if thread_rng().gen_range(0.0..1.0) < fresnel {
+            let wi = wm.mul(2.0 * wo.dot(&wm)) - *wo;
+            if wi.z() <= 0.0 {
+                return None;
+            }
+            wi
+        } else {
+            let wi = vec3::refract(&-*wo, &wm, 1.0 / self.eta);
+            if wi.z() >= 0.0 {
+                return None;
+            }
+            wi
+        };
+
+        // Choosing reflection with probability F cancels the Fresnel term
+        let shadowing = self.distribution.g(wo, &wi) / self.distribution.g1(wo);
+        Some((Color::from(shadowing, shadowing, shadowing), wi))
+    }
+
+    pub fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        let Some((wm, reflect)) = self.half_vector(wo, wi) else {
+            return Color::black();
+        };
+
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let d = self.distribution.d(&wm);
+        let g = self.distribution.g(wo, wi);
+
+        let value = if reflect {
+            fresnel * d * g / (4.0 * wo.z())
+        } else {
+            let denom = wi.dot(&wm) + wo.dot(&wm) / self.eta;
+            (1.0 - fresnel) * d * g * (wi.dot(&wm) * wo.dot(&wm) / (wo.z() * denom * denom)).abs()
+        };
+
+        Color::from(value, value, value)
+    }
+
+    pub fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let Some((wm, reflect)) = self.half_vector(wo, wi) else {
+            return 0.0;
+        };
+
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let visible = self.distribution.visible_d(wo, &wm);
+
+        if reflect {
+            fresnel * visible / (4.0 * wo.dot(&wm).abs())
+        } else {
+            let denom = wi.dot(&wm) + wo.dot(&wm) / self.eta;
+            (1.0 - fresnel) * visible * wi.dot(&wm).abs() / (denom * denom)
+        }
+    }
+
+    // Microfacet normal that scatters `wo` into `wi`, oriented to the macrosurface normal, or
+    // `None` for configurations no microfacet can produce
+    fn half_vector(&self, wo: &Vec3, wi: &Vec3) -> Option<(Vec3, bool)> {
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return None;
+        }
+
+        let reflect = wi.z() > 0.0;
+        let eta = if reflect { 1.0 } else { self.eta };
+        let wm = wi.mul(eta) + *wo;
+        if wm.near_zero() {
+            return None;
+        }
+        let mut wm = wm.unit();
+        if wm.z() < 0.0 {
+            wm = -wm;
+        }
+
+        // Discard back-facing microfacets
+        if wm.dot(wi) * wi.z() < 0.0 || wm.dot(wo) * wo.z() < 0.0 {
+            return None;
+        }
+
+        Some((wm, reflect))
+    }
+}
@@ -0,0 +1,58 @@
+use crate::vec3::Vec3;
+
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    pub fn new(w: &Vec3) -> Self {
+        let unit_w = w.unit();
+        let a = if unit_w.x().abs() > 0.9 {
+            Vec3::from(0.0, 1.0, 0.0)
+        } else {
+            Vec3::from(1.0, 0.0, 0.0)
+        };
+        let v = unit_w.cross(&a).unit();
+        let u = unit_w.cross(&v);
+
+        Self {
+            axis: [u, v, unit_w],
+        }
+    }
+
+    /// Basis around `w` with `u` following `tangent` projected into the plane perpendicular to it
+    pub fn from_tangent(w: &Vec3, tangent: &Vec3) -> Self {
+        let unit_w = w.unit();
+        let u = *tangent - unit_w.mul(unit_w.dot(tangent));
+        if u.near_zero() {
+            return Self::new(w);
+        }
+        let u = u.unit();
+        let v = unit_w.cross(&u);
+
+        Self {
+            axis: [u, v, unit_w],
+        }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.axis[0]
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.axis[1]
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.axis[2]
+    }
+
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        self.u().mul(a.x()) + self.v().mul(a.y()) + self.w().mul(a.z())
+    }
+
+    /// Inverse of `local`, expresses a world space vector in this basis
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::from(a.dot(&self.u()), a.dot(&self.v()), a.dot(&self.w()))
+    }
+}
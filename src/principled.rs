@@ -0,0 +1,216 @@
+//! Disney style principled material
+
+use std::f64::consts::PI;
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    hit::HitRecord,
+    material::{local_directions, BsdfSample, Material},
+    microfacet::{DielectricLobe, Fresnel, Ggx, ReflectionLobe},
+    onb::Onb,
+    ray::Ray,
+    texture::Texture,
+    vec3::{self, Color, Vec3},
+};
+
+/// Disney style material layering a diffuse base with sheen, a specular reflection that becomes
+/// the only lobe as `metallic` goes to one, rough transmission and a clearcoat. Every parameter
+/// other than the index of refraction may be driven by a texture, scalars use its first channel.
+#[derive(Clone)]
+pub struct Principled {
+    /// Diffuse color, or the reflectance of metals
+    pub base_color: Texture,
+    /// Blend from a dielectric at zero to a metal at one
+    pub metallic: Texture,
+    /// Roughness of the specular and transmission lobes, from mirror smooth at zero
+    pub roughness: Texture,
+    /// Scales the normal incidence reflectance of non-metals, 0.5 gives the common 4%
+    pub specular: Texture,
+    /// Blend from an opaque diffuse base at zero to glass at one
+    pub transmission: Texture,
+    /// Index of refraction of the specular and transmission lobes
+    pub ior: f64,
+    /// Strength of a clear varnish layer on top
+    pub clearcoat: Texture,
+    /// Roughness of the clearcoat
+    pub clearcoat_roughness: Texture,
+    /// Soft reflection at grazing angles, as of cloth
+    pub sheen: Texture,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Self {
+            base_color: Texture::scalar(0.8),
+            metallic: Texture::scalar(0.0),
+            roughness: Texture::scalar(0.5),
+            specular: Texture::scalar(0.5),
+            transmission: Texture::scalar(0.0),
+            ior: 1.5,
+            clearcoat: Texture::scalar(0.0),
+            clearcoat_roughness: Texture::scalar(0.03),
+            sheen: Texture::scalar(0.0),
+        }
+    }
+}
+
+// The lobes of a `Principled` material with its textures evaluated at a hit point
+struct Lobes {
+    base_color: Color,
+    diffuse: f64,
+    sheen: f64,
+    specular: ReflectionLobe,
+    specular_weight: f64,
+    transmission: DielectricLobe,
+    transmission_weight: f64,
+    clearcoat: ReflectionLobe,
+    clearcoat_weight: f64,
+}
+
+impl Lobes {
+    // Probabilities of sampling the diffuse, specular, transmission and clearcoat lobes
+    fn probabilities(&self) -> [f64; 4] {
+        let weights = [
+            self.diffuse,
+            self.specular_weight,
+            self.transmission_weight,
+            self.clearcoat_weight * 0.25,
+        ];
+        let total: f64 = weights.iter().sum();
+
+        weights.map(|weight| weight / total)
+    }
+
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        let mut f = Color::black();
+
+        if wi.z() > 0.0 && wo.z() > 0.0 {
+            f += self.base_color.mul(self.diffuse * wi.z() / PI);
+
+            let wm = (*wo + *wi).unit();
+            let sheen = self.sheen * (1.0 - wi.dot(&wm)).clamp(0.0, 1.0).powi(5) * wi.z();
+            f += Color::from(sheen, sheen, sheen);
+        }
+
+        f += self.specular.eval(wo, wi).mul(self.specular_weight);
+
+        let transmission = self.transmission.eval(wo, wi).mul(self.transmission_weight);
+        f += if wi.z() < 0.0 {
+            transmission * self.base_color
+        } else {
+            transmission
+        };
+
+        f += self.clearcoat.eval(wo, wi).mul(self.clearcoat_weight);
+
+        f
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let [diffuse, specular, transmission, clearcoat] = self.probabilities();
+
+        let diffuse_pdf = if wi.z() > 0.0 { wi.z() / PI } else { 0.0 };
+
+        diffuse * diffuse_pdf
+            + specular * self.specular.pdf(wo, wi)
+            + transmission * self.transmission.pdf(wo, wi)
+            + clearcoat * self.clearcoat.pdf(wo, wi)
+    }
+
+    fn sample(&self, wo: &Vec3) -> Option<Vec3> {
+        let [diffuse, specular, transmission, _] = self.probabilities();
+        let choice = thread_rng().gen_range(0.0..1.0);
+
+        if choice < diffuse {
+            Some(vec3::random_cosine_direction())
+        } else if choice < diffuse + specular {
+            self.specular.sample(wo).map(|(_, wi)| wi)
+        } else if choice < diffuse + specular + transmission {
+            self.transmission.sample(wo).map(|(_, wi)| wi)
+        } else {
+            self.clearcoat.sample(wo).map(|(_, wi)| wi)
+        }
+    }
+}
+
+impl Material for Principled {
+    fn sample(&self, r_in: &Ray, record: &HitRecord) -> Option<BsdfSample> {
+        let frame = Onb::new(&record.normal);
+        let wo = frame.to_local(&-r_in.direction().unit());
+        let lobes = self.lobes(record);
+
+        // One-sample MIS over the lobes: whichever lobe picked the direction, weight it by the
+        // full BSDF over the combined density
+        let wi = lobes.sample(&wo)?;
+        let pdf = lobes.pdf(&wo, &wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            ray: Ray::from(record.p, frame.local(&wi)),
+            weight: lobes.eval(&wo, &wi).div(pdf),
+            pdf: Some(pdf),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, record: &HitRecord, scattered: &Ray) -> Color {
+        let (wo, wi) = local_directions(r_in, record, scattered);
+        self.lobes(record).eval(&wo, &wi)
+    }
+
+    fn pdf(&self, r_in: &Ray, record: &HitRecord, scattered: &Ray) -> f64 {
+        let (wo, wi) = local_directions(r_in, record, scattered);
+        self.lobes(record).pdf(&wo, &wi)
+    }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.base_color.value(record.u, record.v, &record.p)
+    }
+}
+
+impl Principled {
+    fn lobes(&self, record: &HitRecord) -> Lobes {
+        let scalar =
+            |texture: &Texture| texture.value(record.u, record.v, &record.p)[0].clamp(0.0, 1.0);
+
+        let base_color = self.base_color.value(record.u, record.v, &record.p);
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let transmission = scalar(&self.transmission) * (1.0 - metallic);
+
+        // Metals tint their reflection by the base color, everything else reflects white
+        let dielectric_f0 = 0.08 * scalar(&self.specular);
+        let f0 = Color::from(dielectric_f0, dielectric_f0, dielectric_f0).mul(1.0 - metallic)
+            + base_color.mul(metallic);
+
+        let eta = if record.front_face {
+            self.ior
+        } else {
+            1.0 / self.ior
+        };
+
+        Lobes {
+            base_color,
+            diffuse: (1.0 - metallic) * (1.0 - scalar(&self.transmission)),
+            sheen: scalar(&self.sheen) * (1.0 - metallic),
+            specular: ReflectionLobe {
+                distribution: Ggx::isotropic(roughness),
+                fresnel: Fresnel::Schlick(f0),
+            },
+            // The transmission lobe includes its own Fresnel reflection
+            specular_weight: 1.0 - transmission,
+            transmission: DielectricLobe {
+                distribution: Ggx::isotropic(roughness),
+                eta,
+            },
+            transmission_weight: transmission,
+            clearcoat: ReflectionLobe {
+                distribution: Ggx::isotropic(scalar(&self.clearcoat_roughness)),
+                fresnel: Fresnel::Schlick(Color::from(0.04, 0.04, 0.04)),
+            },
+            clearcoat_weight: scalar(&self.clearcoat),
+        }
+    }
+}
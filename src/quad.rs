@@ -0,0 +1,109 @@
+//! Flat parallelograms
+
+use std::sync::Arc;
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    hit::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+/// Parallelogram with a corner at `q` and sides `u` and `v`
+#[derive(Clone)]
+pub struct Quad {
+    q: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    normal: Vec3,
+    d: f64,
+    area: f64,
+    material: Arc<dyn Material>,
+}
+
+impl Quad {
+    /// Quad made of `material`, facing the side `u` × `v` points to
+    pub fn new(q: Vec3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.unit();
+        let d = normal.dot(&q);
+        let w = n.div(n.dot(&n));
+
+        Self {
+            q,
+            u,
+            v,
+            w,
+            normal,
+            d,
+            area: n.length(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction());
+
+        // Ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(ray.origin())) / denom;
+        if !ray_t.contains(t) {
+            return None;
+        }
+
+        // Determine if the hit point lies within the quad using its planar coordinates
+        let p = ray.at(t);
+        let planar_hitpt = p - self.q;
+        let alpha = self.w.dot(&planar_hitpt.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar_hitpt));
+
+        let unit = Interval::new(0.0, 1.0);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        Some(HitRecord::new(
+            ray,
+            p,
+            self.normal,
+            t,
+            (alpha, beta),
+            (self.u, self.v),
+            self.material.clone(),
+        ))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let Some(record) = self.hit(
+            &Ray::from(*origin, *direction),
+            &Interval::new(0.001, f64::INFINITY),
+        ) else {
+            return 0.0;
+        };
+
+        let distance_squared = record.t * record.t * direction.length_squared();
+        let cosine = (direction.dot(&record.normal) / direction.length()).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let mut rng = thread_rng();
+        let p = self.q + self.u.mul(rng.gen_range(0.0..1.0)) + self.v.mul(rng.gen_range(0.0..1.0));
+
+        p - *origin
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        materials.push(self.material.clone());
+    }
+}
@@ -1,23 +1,47 @@
+//! Rays of light
+
 use crate::vec3::Vec3;
 
+/// Half-line from `origin` along `direction`, which need not be a unit vector
+#[derive(Clone, Copy)]
 pub struct Ray {
     origin: Vec3,
     direction: Vec3,
+    // Wavelength in nanometres carried by paths when rendering spectrally
+    wavelength: Option<f64>,
 }
 
 impl Ray {
+    /// Ray without a wavelength, carrying RGB
     pub fn from(origin: Vec3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            wavelength: None,
+        }
+    }
+
+    /// Ray carrying the given wavelength in nanometres, or RGB when it's `None`
+    pub fn with_wavelength(self, wavelength: Option<f64>) -> Self {
+        Self { wavelength, ..self }
     }
 
+    /// Point the ray starts from
     pub fn origin(&self) -> &Vec3 {
         &self.origin
     }
 
+    /// Direction the ray travels in
     pub fn direction(&self) -> &Vec3 {
         &self.direction
     }
 
+    /// Wavelength in nanometres carried when rendering spectrally
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
+    /// Point at parameter `t` along the ray
     pub fn at(&self, t: f64) -> Vec3 {
         self.origin + (self.direction.mul(t))
     }
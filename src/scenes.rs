@@ -0,0 +1,777 @@
+use std::sync::Arc;
+
+use rand::{thread_rng, Rng};
+use weekend_raytracer::{
+    animation::{Animated, CameraAnimation, Interpolation, Track, Transform},
+    aperture::Aperture,
+    bump::{BumpMap, NormalMap},
+    camera::{CameraBuilder, Physical},
+    hit::{HitList, Hittable},
+    mask::{AlphaMask, AlphaMode},
+    material::{
+        Dielectric, DiffuseLight, Dispersion, Lambertian, Material, Medium, Metal, RoughConductor,
+        RoughDielectric, Subsurface, ThinFilm,
+    },
+    principled::Principled,
+    quad::Quad,
+    sphere::Sphere,
+    texture::Texture,
+    vec3::{Color, Vec3},
+};
+
+// World to render, the objects to sample as lights and a camera to set up further from the
+// command line
+pub struct Scene {
+    pub world: HitList<Box<dyn Hittable>>,
+    pub lights: HitList<Box<dyn Hittable>>,
+    pub camera: CameraBuilder,
+}
+
+// Sample scene used while implementing
+pub fn test_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.8, 0.8, 0.0)));
+    let center = Arc::new(Lambertian::new(Color::from(0.1, 0.2, 0.5)));
+    let left = Arc::new(Dielectric::new(1.5));
+    let right = Arc::new(Metal::new(Color::from(0.8, 0.6, 0.2), 0.0));
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, center));
+    world.add(Sphere::new(Vec3::from(-1.0, 0.0, -1.0), 0.5, left.clone()));
+    world.add(Sphere::new(Vec3::from(-1.0, 0.0, -1.0), -0.4, left));
+    world.add(Sphere::new(Vec3::from(1.0, 0.0, -1.0), 0.5, right));
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(-2.0, 2.0, 1.0));
+    camera.v_fov(20.0);
+    camera.samples(25);
+    camera.defocus_angle(10.0);
+    camera.focus_dist(3.4);
+
+    Scene {
+        world,
+        lights: HitList::new(),
+        camera,
+    }
+}
+
+// Sample scene lit only by emissive objects
+pub fn lights_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.8, 0.8, 0.0)));
+    let center = Arc::new(Lambertian::new(Color::from(0.1, 0.2, 0.5)));
+    let left = Arc::new(Dielectric::new(1.5));
+    let right = Arc::new(Metal::new(Color::from(0.8, 0.6, 0.2), 0.0));
+    let panel = Arc::new(DiffuseLight::new(Color::from(4.0, 4.0, 4.0)));
+    let bulb = Arc::new(DiffuseLight::new(Color::from(20.0, 12.0, 6.0)));
+
+    // Lights
+    let panel = Quad::new(
+        Vec3::from(-1.0, 2.0, -2.0),
+        Vec3::from(2.0, 0.0, 0.0),
+        Vec3::from(0.0, 0.0, 1.0),
+        panel,
+    );
+    let bulb = Sphere::new(Vec3::from(0.6, -0.35, -0.4), 0.1, bulb);
+
+    let mut lights = HitList::new();
+    lights.add(panel.clone());
+    lights.add(bulb.clone());
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, center));
+    world.add(Sphere::new(Vec3::from(-1.0, 0.0, -1.0), 0.5, left.clone()));
+    world.add(Sphere::new(Vec3::from(-1.0, 0.0, -1.0), -0.4, left));
+    world.add(Sphere::new(Vec3::from(1.0, 0.0, -1.0), 0.5, right));
+    world.add(panel);
+    world.add(bulb);
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(-2.0, 2.0, 1.0));
+    camera.v_fov(30.0);
+    camera.samples(25);
+    camera.background(Color::black());
+
+    Scene {
+        world,
+        lights,
+        camera,
+    }
+}
+
+// Rough conductors and glass lit by the sky and an area light
+pub fn materials_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.5, 0.5, 0.5)));
+    let gold = Arc::new(RoughConductor::new(
+        Color::from(0.143, 0.374, 1.442),
+        Color::from(3.983, 2.385, 1.603),
+        0.3,
+    ));
+    let frosted = Arc::new(
+        RoughDielectric::new(1.5, 0.2)
+            .with_absorption(Medium::from_transmittance(Color::from(0.8, 0.9, 1.0), 1.0)),
+    );
+    let bottle = Arc::new(
+        Dielectric::new(1.5)
+            .with_absorption(Medium::from_transmittance(Color::from(0.3, 0.7, 0.4), 0.5)),
+    );
+    let copper = Arc::new(RoughConductor::new(
+        Color::from(0.200, 0.924, 1.102),
+        Color::from(3.912, 2.452, 2.142),
+        0.1,
+    ));
+    let panel = Arc::new(DiffuseLight::new(Color::from(4.0, 4.0, 4.0)));
+
+    // Lights
+    let panel = Quad::new(
+        Vec3::from(-1.5, 2.5, -2.0),
+        Vec3::from(3.0, 0.0, 0.0),
+        Vec3::from(0.0, 0.0, 1.0),
+        panel,
+    );
+    let mut lights = HitList::new();
+    lights.add(panel.clone());
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(-1.0, 0.0, -1.0), 0.5, gold));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, frosted));
+    world.add(Sphere::new(Vec3::from(1.0, 0.0, -1.0), 0.5, copper));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -2.2), 0.5, bottle));
+    world.add(panel);
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(0.0, 1.0, 2.0));
+    camera.v_fov(40.0);
+    camera.samples(25);
+
+    Scene {
+        world,
+        lights,
+        camera,
+    }
+}
+
+// Principled material variations on a checkered floor
+pub fn principled_scene() -> Scene {
+    let checker = Texture::checker(0.5, Color::from(0.2, 0.3, 0.1), Color::from(0.9, 0.9, 0.9));
+    let ground = Principled {
+        base_color: checker,
+        roughness: Texture::checker(0.5, 0.6, 0.1),
+        ..Default::default()
+    };
+    let plastic = Principled {
+        base_color: Color::from(0.7, 0.1, 0.1).into(),
+        roughness: 0.3.into(),
+        clearcoat: 1.0.into(),
+        ..Default::default()
+    };
+    let brushed = Principled {
+        base_color: Color::from(0.9, 0.6, 0.3).into(),
+        metallic: 1.0.into(),
+        roughness: 0.35.into(),
+        ..Default::default()
+    };
+    let glass = Principled {
+        base_color: Color::from(0.8, 0.95, 0.9).into(),
+        roughness: 0.05.into(),
+        transmission: 1.0.into(),
+        ..Default::default()
+    };
+    let velvet = Principled {
+        base_color: Color::from(0.2, 0.05, 0.3).into(),
+        roughness: 0.9.into(),
+        specular: 0.0.into(),
+        sheen: 1.0.into(),
+        ..Default::default()
+    };
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(
+        Vec3::from(0.0, -100.5, -1.0),
+        100.0,
+        Arc::new(ground),
+    ));
+    for (x, material) in [
+        (-1.65, plastic),
+        (-0.55, brushed),
+        (0.55, glass),
+        (1.65, velvet),
+    ] {
+        world.add(Sphere::new(
+            Vec3::from(x, 0.0, -1.0),
+            0.5,
+            Arc::new(material),
+        ));
+    }
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(0.0, 1.0, 3.0));
+    camera.v_fov(40.0);
+    camera.samples(25);
+
+    Scene {
+        world,
+        lights: HitList::new(),
+        camera,
+    }
+}
+
+// Dispersive glass and diamond lit from above, rendered spectrally
+pub fn dispersion_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.8, 0.8, 0.8)));
+    let crown = Arc::new(Dielectric::new(1.5).with_dispersion(Dispersion::BK7));
+    let flint = Arc::new(Dielectric::new(1.5).with_dispersion(Dispersion::SF11));
+    let diamond = Arc::new(Dielectric::new(1.5).with_dispersion(Dispersion::DIAMOND));
+    let frosted = Arc::new(
+        RoughDielectric::new(1.5, 0.1).with_dispersion(Dispersion::Cauchy { a: 1.67, b: 0.0074 }),
+    );
+    let panel = Arc::new(DiffuseLight::new(Color::from(15.0, 15.0, 15.0)));
+
+    // Lights
+    let panel = Quad::new(
+        Vec3::from(-0.5, 3.0, -1.5),
+        Vec3::from(1.0, 0.0, 0.0),
+        Vec3::from(0.0, 0.0, 0.5),
+        panel,
+    );
+    let mut lights = HitList::new();
+    lights.add(panel.clone());
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(-1.65, 0.0, -1.0), 0.5, crown));
+    world.add(Sphere::new(Vec3::from(-0.55, 0.0, -1.0), 0.5, flint));
+    world.add(Sphere::new(Vec3::from(0.55, 0.0, -1.0), 0.5, diamond));
+    world.add(Sphere::new(Vec3::from(1.65, 0.0, -1.0), 0.5, frosted));
+    world.add(panel);
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(0.0, 1.5, 3.0));
+    camera.v_fov(40.0);
+    camera.samples(100);
+    camera.background(Color::from(0.05, 0.05, 0.05));
+    camera.spectral(true);
+
+    Scene {
+        world,
+        lights,
+        camera,
+    }
+}
+
+// Soap bubbles and heat tinted steel, colored by interference in thin films
+pub fn thin_film_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.3, 0.3, 0.35)));
+    let bubble = |thickness: f64| {
+        let film = ThinFilm {
+            thickness: Texture::scalar(thickness),
+            ior: 1.33,
+        };
+        Arc::new(Dielectric::new(1.0).with_thin_film(film))
+    };
+    let steel = Arc::new(
+        RoughConductor::new(
+            Color::from(2.87, 2.95, 2.65),
+            Color::from(3.19, 2.93, 2.81),
+            0.15,
+        )
+        .with_thin_film(ThinFilm {
+            thickness: Texture::checker(0.2, 250.0, 400.0),
+            ior: 2.5,
+        }),
+    );
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(-1.1, 0.0, -1.0), 0.5, bubble(300.0)));
+    world.add(Sphere::new(Vec3::from(0.0, 0.2, -1.6), 0.6, bubble(450.0)));
+    world.add(Sphere::new(Vec3::from(1.1, 0.0, -1.0), 0.5, steel));
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.2));
+    camera.look_from(Vec3::from(0.0, 0.8, 2.5));
+    camera.v_fov(40.0);
+    camera.samples(50);
+
+    Scene {
+        world,
+        lights: HitList::new(),
+        camera,
+    }
+}
+
+// Tiles and faceted spheres carved out by bump and normal maps rather than geometry
+pub fn mapping_scene() -> Scene {
+    // Materials
+    let tiles = Arc::new(Lambertian::new(Color::from(0.6, 0.55, 0.5)));
+    let gold = Arc::new(RoughConductor::new(
+        Color::from(0.143, 0.374, 1.442),
+        Color::from(3.983, 2.385, 1.603),
+        0.2,
+    ));
+    let clay = Arc::new(Lambertian::new(Color::from(0.7, 0.2, 0.1)));
+    let panel = Arc::new(DiffuseLight::new(Color::from(4.0, 4.0, 4.0)));
+
+    // Lights
+    let panel = Quad::new(
+        Vec3::from(-1.5, 2.5, -2.0),
+        Vec3::from(3.0, 0.0, 0.0),
+        Vec3::from(0.0, 0.0, 1.0),
+        panel,
+    );
+    let mut lights = HitList::new();
+    lights.add(panel.clone());
+
+    // World
+    let floor = Quad::new(
+        Vec3::from(-5.0, -0.5, -6.0),
+        Vec3::from(0.0, 0.0, 10.0),
+        Vec3::from(10.0, 0.0, 0.0),
+        tiles,
+    );
+    let facets = Texture::checker(
+        0.15,
+        Color::from(0.65, 0.5, 0.9),
+        Color::from(0.35, 0.5, 0.9),
+    );
+    let dimples = Texture::checker(0.1, 0.0, 1.0);
+
+    let mut world = HitList::new();
+    world.add(BumpMap::new(floor, Texture::checker(0.5, 0.0, 1.0), 0.05));
+    world.add(NormalMap::new(
+        Sphere::new(Vec3::from(-0.6, 0.0, -1.0), 0.5, gold),
+        facets,
+    ));
+    world.add(BumpMap::new(
+        Sphere::new(Vec3::from(0.6, 0.0, -1.0), 0.5, clay),
+        dimples,
+        0.008,
+    ));
+    world.add(panel);
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(0.0, 1.0, 2.0));
+    camera.v_fov(40.0);
+    camera.samples(25);
+
+    Scene {
+        world,
+        lights,
+        camera,
+    }
+}
+
+// A lattice fence and a sheet of gauze in front of a sphere, cut out by alpha masks
+pub fn cutout_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.5, 0.5, 0.5)));
+    let wood = Arc::new(Lambertian::new(Color::from(0.45, 0.3, 0.15)));
+    let gauze = Arc::new(Lambertian::new(Color::from(0.8, 0.1, 0.1)));
+    let ball = Arc::new(Lambertian::new(Color::from(0.1, 0.3, 0.7)));
+    let sun = Arc::new(DiffuseLight::new(Color::from(10.0, 10.0, 10.0)));
+
+    // Lights
+    let sun = Quad::new(
+        Vec3::from(-1.0, 3.0, 0.0),
+        Vec3::from(1.0, 0.0, 0.0),
+        Vec3::from(0.0, 0.0, 1.0),
+        sun,
+    );
+    let mut lights = HitList::new();
+    lights.add(sun.clone());
+
+    // World
+    let fence = Quad::new(
+        Vec3::from(-1.5, -0.5, -0.55),
+        Vec3::from(1.5, 0.0, 0.0),
+        Vec3::from(0.0, 1.2, 0.0),
+        wood,
+    );
+    let sheet = Quad::new(
+        Vec3::from(0.1, -0.5, -0.3),
+        Vec3::from(1.2, 0.0, 0.0),
+        Vec3::from(0.0, 1.0, 0.0),
+        gauze,
+    );
+
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.2), 0.5, ball));
+    world.add(AlphaMask::new(
+        fence,
+        Texture::checker(0.1, 0.0, 1.0),
+        AlphaMode::Threshold(0.5),
+    ));
+    world.add(AlphaMask::new(
+        sheet,
+        Texture::scalar(0.4),
+        AlphaMode::Stochastic,
+    ));
+    world.add(sun);
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(0.0, 0.6, 2.0));
+    camera.v_fov(40.0);
+    camera.samples(50);
+
+    Scene {
+        world,
+        lights,
+        camera,
+    }
+}
+
+// Brushed aluminium, stretched along and across the tangent and following a flow map
+pub fn brushed_scene() -> Scene {
+    // Materials
+    let (eta, k) = (Color::from(1.66, 0.88, 0.52), Color::from(9.22, 6.27, 4.84));
+    let ground = Arc::new(Lambertian::new(Color::from(0.4, 0.4, 0.4)));
+    let along = Arc::new(RoughConductor::anisotropic(eta, k, 0.05, 0.4));
+    let across = Arc::new(RoughConductor::anisotropic(eta, k, 0.4, 0.05));
+    let flow = Texture::checker(0.2, Color::from(1.0, 0.5, 0.0), Color::from(0.5, 1.0, 0.0));
+    let patched = Arc::new(RoughConductor::anisotropic(eta, k, 0.05, 0.4).with_flow_map(flow));
+    let panel = Arc::new(DiffuseLight::new(Color::from(4.0, 4.0, 4.0)));
+
+    // Lights
+    let panel = Quad::new(
+        Vec3::from(-1.5, 2.5, -2.0),
+        Vec3::from(3.0, 0.0, 0.0),
+        Vec3::from(0.0, 0.0, 1.0),
+        panel,
+    );
+    let mut lights = HitList::new();
+    lights.add(panel.clone());
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(-1.1, 0.0, -1.0), 0.5, along));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, across));
+    world.add(Sphere::new(Vec3::from(1.1, 0.0, -1.0), 0.5, patched));
+    world.add(panel);
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(0.0, 1.0, 2.0));
+    camera.v_fov(40.0);
+    camera.samples(25);
+
+    Scene {
+        world,
+        lights,
+        camera,
+    }
+}
+
+// Skin, wax and marble under the sky, softened by light scattering beneath their surfaces
+pub fn subsurface_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.4, 0.4, 0.4)));
+    let skin = Arc::new(Subsurface::new(
+        Color::from(0.85, 0.6, 0.5),
+        Color::from(0.12, 0.07, 0.05),
+        1.4,
+    ));
+    let wax = Arc::new(Subsurface::new(
+        Color::from(0.95, 0.8, 0.5),
+        Color::from(0.2, 0.15, 0.1),
+        1.45,
+    ));
+    let marble = Arc::new(Subsurface::new(
+        Color::from(0.9, 0.9, 0.88),
+        Color::from(0.05, 0.05, 0.05),
+        1.5,
+    ));
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(-1.1, 0.0, -1.0), 0.5, skin));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, wax));
+    world.add(Sphere::new(Vec3::from(1.1, 0.0, -1.0), 0.5, marble));
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.0, -1.0));
+    camera.look_from(Vec3::from(0.0, 0.6, 2.0));
+    camera.v_fov(40.0);
+    camera.samples(50);
+
+    Scene {
+        world,
+        lights: HitList::new(),
+        camera,
+    }
+}
+
+// Out of focus string lights behind a sphere, showing off the shape of the aperture
+pub fn bokeh_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.3, 0.3, 0.3)));
+    let subject = Arc::new(Metal::new(Color::from(0.8, 0.5, 0.3), 0.1));
+    let panel = Arc::new(DiffuseLight::new(Color::from(6.0, 6.0, 6.0)));
+
+    // Lights
+    let panel = Quad::new(
+        Vec3::from(-1.0, 2.0, 0.0),
+        Vec3::from(2.0, 0.0, 0.0),
+        Vec3::from(0.0, 0.0, 1.0),
+        panel,
+    );
+
+    let mut lights = HitList::new();
+    lights.add(panel.clone());
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.5, -1.0), 100.0, ground));
+    world.add(Sphere::new(Vec3::from(0.0, 0.0, -1.0), 0.5, subject));
+    world.add(panel);
+
+    // Two sagging strings of bulbs far behind the subject
+    let mut rng = thread_rng();
+    for row in 0..2 {
+        for i in 0..24 {
+            let x = -6.0 + i as f64 * 0.5 + row as f64 * 0.25;
+            let y = 1.2 + row as f64 * 0.8 + 0.02 * x * x;
+            let z = -10.0 - row as f64 * 2.0;
+            let color = Color::from(
+                rng.gen_range(0.6..1.0),
+                rng.gen_range(0.4..0.9),
+                rng.gen_range(0.2..0.6),
+            );
+            let bulb = Arc::new(DiffuseLight::new(color.mul(40.0)));
+            world.add(Sphere::new(Vec3::from(x, y, z), 0.03, bulb));
+        }
+    }
+
+    // Camera
+    let mut camera = CameraBuilder::new();
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.3, -1.0));
+    camera.look_from(Vec3::from(0.0, 0.3, 2.0));
+    camera.v_fov(40.0);
+    camera.samples(100);
+    camera.background(Color::black());
+    camera.focus_dist(3.0);
+    camera.defocus_angle(3.0);
+    camera.aperture(Aperture::Polygon {
+        blades: 6,
+        rotation: 0.0,
+    });
+
+    Scene {
+        world,
+        lights,
+        camera,
+    }
+}
+
+// Row of spheres receding from a full frame camera with a fast portrait lens
+pub fn portrait_scene() -> Scene {
+    // Materials
+    let ground = Arc::new(Lambertian::new(Color::from(0.5, 0.5, 0.5)));
+    let colors = [
+        Color::from(0.7, 0.2, 0.2),
+        Color::from(0.8, 0.6, 0.2),
+        Color::from(0.2, 0.6, 0.3),
+        Color::from(0.2, 0.4, 0.7),
+        Color::from(0.5, 0.3, 0.6),
+    ];
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -1000.0, 0.0), 1000.0, ground));
+    for (i, color) in colors.into_iter().enumerate() {
+        let z = -1.5 * i as f64;
+        let x = if i % 2 == 0 { -0.2 } else { 0.2 };
+        let sphere = Arc::new(Lambertian::new(color));
+        world.add(Sphere::new(Vec3::from(x, 0.15, z), 0.15, sphere));
+    }
+
+    // Camera, an 85mm lens wide open focused on the second sphere, with an exposure a
+    // photographer might pick on a sunny day
+    let mut camera = CameraBuilder::physical(&Physical {
+        sensor: (36.0, 24.0),
+        focal_length: 85.0,
+        f_stop: 1.4,
+        focus_dist: 4.5,
+        iso: 50.0,
+        shutter: 1.0 / 8000.0,
+    });
+    camera.image_width(800);
+    camera.look_at(Vec3::from(0.0, 0.15, -3.0));
+    camera.look_from(Vec3::from(0.0, 0.5, 3.0));
+    camera.samples(100);
+
+    Scene {
+        world,
+        lights: HitList::new(),
+        camera,
+    }
+}
+
+// Ball bouncing past a spinning panel while the camera swings around them, for rendering with
+// `--frames 0:47`
+pub fn animation_scene() -> Scene {
+    // Materials
+    let checker = Texture::checker(0.5, Color::from(0.2, 0.3, 0.1), Color::from(0.9, 0.9, 0.9));
+    let ground = Arc::new(Principled {
+        base_color: checker,
+        ..Default::default()
+    });
+    let ball = Arc::new(Metal::new(Color::from(0.8, 0.3, 0.2), 0.2));
+    let panel = Arc::new(Lambertian::new(Color::from(0.2, 0.4, 0.8)));
+
+    // World
+    let mut world = HitList::new();
+    world.add(Sphere::new(Vec3::from(0.0, -100.0, 0.0), 100.0, ground));
+
+    // Keys at the top and bottom of each bounce, smoothed into arcs by the spline
+    let bounce = Track::new(Interpolation::Spline, 0.0, Vec3::from(-1.5, 1.5, 0.0))
+        .with_key(0.5, Vec3::from(-0.75, 0.3, 0.0))
+        .with_key(1.0, Vec3::from(0.0, 1.2, 0.0))
+        .with_key(1.5, Vec3::from(0.75, 0.3, 0.0))
+        .with_key(2.0, Vec3::from(1.5, 1.0, 0.0));
+    let ball = Sphere::new(Vec3::new(), 0.3, ball);
+    world.add(Animated::new(
+        ball,
+        Transform::new().with_translation(bounce),
+    ));
+
+    let spin = Track::new(Interpolation::Linear, 0.0, Vec3::new())
+        .with_key(2.0, Vec3::from(0.0, 360.0, 0.0));
+    let panel = Quad::new(
+        Vec3::from(-0.5, 0.0, 0.0),
+        Vec3::from(1.0, 0.0, 0.0),
+        Vec3::from(0.0, 1.0, 0.0),
+        panel,
+    );
+    world.add(Animated::new(
+        panel,
+        Transform::new()
+            .with_rotation(spin)
+            .with_translation(Track::constant(Vec3::from(0.0, 0.0, -1.0))),
+    ));
+
+    // Camera
+    let orbit = Track::new(Interpolation::Spline, 0.0, Vec3::from(-3.0, 1.5, 4.0))
+        .with_key(1.0, Vec3::from(0.0, 2.5, 5.0))
+        .with_key(2.0, Vec3::from(3.0, 1.5, 4.0));
+    let zoom = Track::new(Interpolation::Linear, 0.0, 45.0).with_key(2.0, 35.0);
+
+    let mut camera = CameraBuilder::new();
+    camera.image_width(400);
+    camera.look_at(Vec3::from(0.0, 0.5, -0.5));
+    camera.samples(20);
+    camera.animation(
+        CameraAnimation::default()
+            .with_look_from(orbit)
+            .with_v_fov(zoom),
+    );
+
+    Scene {
+        world,
+        lights: HitList::new(),
+        camera,
+    }
+}
+
+pub fn cover_art() -> Scene {
+    let mut world = HitList::new();
+
+    let ground = Arc::new(Lambertian::new(Color::from(0.5, 0.5, 0.5)));
+    world.add(Sphere::new(Vec3::from(0.0, -1000.0, 0.0), 1000.0, ground));
+
+    let mut rng = thread_rng();
+    let center_offset = Vec3::from(4.0, 0.2, 0.0);
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = rng.gen_range(0.0..1.0);
+            let center = Vec3::from(
+                (a as f64) + 0.9 * rng.gen_range(0.0..1.0),
+                0.2,
+                (b as f64) + 0.9 * rng.gen_range(0.0..1.0),
+            );
+
+            if (center - center_offset).length() > 0.9 {
+                let mat: Arc<dyn Material> = match choose_mat {
+                    _x if _x < 0.8 => {
+                        let albedo = Color::random_color() * Color::random_color();
+                        Arc::new(Lambertian::new(albedo))
+                    }
+                    _x if _x < 0.95 => {
+                        let albedo = Color::random_color();
+                        let fuzz = rng.gen_range(0.0..0.5);
+                        Arc::new(Metal::new(albedo, fuzz))
+                    }
+                    _ => Arc::new(Dielectric::new(1.5)),
+                };
+                world.add(Sphere::new(center, 0.2, mat));
+            }
+        }
+    }
+
+    let mat1 = Arc::new(Dielectric::new(1.5));
+    let mat2 = Arc::new(Lambertian::new(Color::from(0.4, 0.2, 0.1)));
+    let mat3 = Arc::new(Metal::new(Color::from(0.7, 0.6, 0.5), 0.0));
+
+    world.add(Sphere::new(Vec3::from(0.0, 1.0, 0.0), 1.0, mat1));
+    world.add(Sphere::new(Vec3::from(-4.0, 1.0, 0.0), 1.0, mat2));
+    world.add(Sphere::new(Vec3::from(4.0, 1.0, 0.0), 1.0, mat3));
+
+    let mut camera = CameraBuilder::new();
+    camera.image_width(1200);
+    camera.samples(500);
+    camera.v_fov(20.0);
+    camera.look_from(Vec3::from(13.0, 2.0, 3.0));
+    camera.look_at(Vec3::from(0.0, 0.0, 0.0));
+    camera.v_up(Vec3::from(0.0, 1.0, 0.0));
+    camera.defocus_angle(0.6);
+    camera.focus_dist(10.0);
+
+    Scene {
+        world,
+        lights: HitList::new(),
+        camera,
+    }
+}
@@ -0,0 +1,50 @@
+//! Animated image output
+
+use std::{fs::File, io::BufWriter, path::Path};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, DynamicImage, Frame, ImageResult, RgbImage,
+};
+
+/// Writes `frames` as a looping animated GIF played at `fps` frames per second
+pub fn save_gif(frames: &[RgbImage], path: impl AsRef<Path>, fps: f64) -> ImageResult<()> {
+    let file = BufWriter::new(File::create(path)?);
+    // Trades some palette quality for speed, quantising every frame is slow at the best setting
+    let mut encoder = GifEncoder::new_with_speed(file, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    let delay = Delay::from_numer_denom_ms(1000, fps.round().max(1.0) as u32);
+    for frame in frames {
+        let rgba = DynamicImage::ImageRgb8(frame.clone()).into_rgba8();
+        encoder.encode_frame(Frame::from_parts(rgba, 0, 0, delay))?;
+    }
+
+    Ok(())
+}
+
+/// Writes `frames`, all of the same size, as a looping animated PNG played at `fps` frames per
+/// second. Unlike GIFs these keep full color.
+pub fn save_apng(
+    frames: &[RgbImage],
+    path: impl AsRef<Path>,
+    fps: f64,
+) -> Result<(), png::EncodingError> {
+    let Some(first) = frames.first() else {
+        return Ok(());
+    };
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, first.width(), first.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay((1000.0 / fps).round() as u16, 1000)?;
+
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.as_raw())?;
+    }
+
+    writer.finish()
+}
@@ -0,0 +1,131 @@
+use rand::{thread_rng, Rng};
+use std::sync::OnceLock;
+
+use crate::vec3::Color;
+
+/// Visible range wavelengths are sampled from, in nanometres
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+
+/// Wavelength of the sodium D line, where a dispersive material's nominal IOR is quoted
+pub const LAMBDA_D: f64 = 587.6;
+
+/// Wavelengths standing in for the red, green and blue channels of RGB renders
+pub const RGB_WAVELENGTHS: [f64; 3] = [650.0, 510.0, 475.0];
+
+pub fn sample_wavelength() -> f64 {
+    thread_rng().gen_range(LAMBDA_MIN..LAMBDA_MAX)
+}
+
+/// Value at `lambda` of a smooth spectrum whose linear sRGB color is `color`
+pub fn upsample(color: Color, lambda: f64) -> f64 {
+    let weights = weights();
+    let basis = basis(lambda);
+
+    let mut value = 0.0;
+    for i in 0..3 {
+        let coefficient: f64 = (0..3).map(|k| weights[i][k] * color[k]).sum();
+        value += coefficient * basis[i];
+    }
+    value
+}
+
+/// Linear sRGB estimate of a spectrum from its `value` at a wavelength drawn by
+/// `sample_wavelength`. Averaging these over wavelengths gives back the color `upsample` was
+/// given for spectra that went through unchanged.
+pub fn to_rgb(value: f64, lambda: f64) -> Color {
+    rgb_matching(lambda).mul(value * (LAMBDA_MAX - LAMBDA_MIN))
+}
+
+// CIE 1931 color matching functions, using the multi-lobe fit from Wyman, Sloan and Shirley,
+// "Simple Analytic Approximations to the CIE XYZ Color Matching Functions"
+fn xyz_matching(lambda: f64) -> Color {
+    let lobe = |mu: f64, sigma_low: f64, sigma_high: f64| {
+        let sigma = if lambda < mu { sigma_low } else { sigma_high };
+        let t = (lambda - mu) / sigma;
+        (-0.5 * t * t).exp()
+    };
+
+    Color::from(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+// Matching functions for linear sRGB, white balanced so that a spectrum of one everywhere is
+// white rather than the pink the equal energy illuminant is under D65
+fn rgb_matching(lambda: f64) -> Color {
+    static BALANCE: OnceLock<Color> = OnceLock::new();
+    let balance = BALANCE.get_or_init(|| {
+        let mut balance = Color::black();
+        for channel in 0..3 {
+            balance[channel] = 1.0 / integrate(|lambda| xyz_to_rgb(xyz_matching(lambda))[channel]);
+        }
+        balance
+    });
+
+    xyz_to_rgb(xyz_matching(lambda)) * *balance
+}
+
+fn xyz_to_rgb(xyz: Color) -> Color {
+    Color::from(
+        3.2406 * xyz[0] - 1.5372 * xyz[1] - 0.4986 * xyz[2],
+        -0.9689 * xyz[0] + 1.8758 * xyz[1] + 0.0415 * xyz[2],
+        0.0557 * xyz[0] - 0.2040 * xyz[1] + 1.0570 * xyz[2],
+    )
+}
+
+// Smooth blue, green and red bands summing to one everywhere, which spectra are built from
+fn basis(lambda: f64) -> [f64; 3] {
+    let smoothstep = |from: f64, to: f64| {
+        let t = ((lambda - from) / (to - from)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    };
+
+    let blue = 1.0 - smoothstep(470.0, 520.0);
+    let red = smoothstep(570.0, 620.0);
+    [red, 1.0 - red - blue, blue]
+}
+
+// Maps a color onto basis coefficients, chosen so that integrating the resulting spectrum
+// against `rgb_matching` gives the color back exactly
+fn weights() -> &'static [[f64; 3]; 3] {
+    static WEIGHTS: OnceLock<[[f64; 3]; 3]> = OnceLock::new();
+    WEIGHTS.get_or_init(|| {
+        // projection[k][i] is channel k of basis band i
+        let mut projection = [[0.0; 3]; 3];
+        for (k, row) in projection.iter_mut().enumerate() {
+            for (i, entry) in row.iter_mut().enumerate() {
+                *entry = integrate(|lambda| basis(lambda)[i] * rgb_matching(lambda)[k]);
+            }
+        }
+        invert(&projection)
+    })
+}
+
+fn integrate(f: impl Fn(f64) -> f64) -> f64 {
+    let steps = 800;
+    let step = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+    (0..steps)
+        .map(|i| f(LAMBDA_MIN + (i as f64 + 0.5) * step) * step)
+        .sum()
+}
+
+fn invert(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let cofactor = |r: usize, c: usize| {
+        let (r0, r1) = ((r + 1) % 3, (r + 2) % 3);
+        let (c0, c1) = ((c + 1) % 3, (c + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let det: f64 = (0..3).map(|c| m[0][c] * cofactor(0, c)).sum();
+
+    let mut inverse = [[0.0; 3]; 3];
+    for (r, row) in inverse.iter_mut().enumerate() {
+        for (c, entry) in row.iter_mut().enumerate() {
+            *entry = cofactor(c, r) / det;
+        }
+    }
+    inverse
+}
@@ -1,32 +1,66 @@
+//! Spheres
+
+use std::{f64::consts::PI, sync::Arc};
+
+use rand::{thread_rng, Rng};
+
 use crate::{
     hit::{HitRecord, Hittable},
     interval::Interval,
     material::Material,
+    onb::Onb,
     ray::Ray,
     vec3::Vec3,
 };
 
+/// Sphere with latitude and longitude surface coordinates, sampled by the solid angle it
+/// subtends when used as a light. A negative radius turns its normals inwards.
+#[derive(Clone)]
 pub struct Sphere {
     center: Vec3,
     radius: f64,
-    material: Material,
+    material: Arc<dyn Material>,
 }
 
 impl Sphere {
-    pub fn new(center: Vec3, radius: f64, material: Material) -> Self {
+    /// Sphere around `center`, made of `material`
+    pub fn new(center: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
         Self {
             center,
             radius,
             material,
         }
     }
+
+    // Maps a point on the unit sphere to latitude and longitude, with u starting from -x and v
+    // running from the bottom pole to the top
+    fn uv(p: &Vec3) -> (f64, f64) {
+        let theta = (-p.y()).clamp(-1.0, 1.0).acos();
+        let phi = (-p.z()).atan2(p.x()) + PI;
+
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    // Derivatives of the surface point with respect to `uv` at the unit normal `p`
+    fn tangents(&self, p: &Vec3) -> (Vec3, Vec3) {
+        let sin_theta = (1.0 - p.y() * p.y()).sqrt().max(1e-8);
+        let dpdu = Vec3::from(p.z(), 0.0, -p.x()).mul(2.0 * PI * self.radius);
+        let dpdv = Vec3::from(
+            -p.x() * p.y() / sin_theta,
+            sin_theta,
+            -p.z() * p.y() / sin_theta,
+        )
+        .mul(PI * self.radius);
+
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
         let oc = ray.origin() - &self.center;
         let a = ray.direction().length_squared();
-        let half_b = oc.dot(&ray.direction());
+        let half_b = oc.dot(ray.direction());
         let c = oc.length_squared() - self.radius * self.radius;
         let discriminant = half_b * half_b - a * c;
 
@@ -46,6 +80,62 @@ impl Hittable for Sphere {
         let p = ray.at(root);
         let normal = (p - self.center).div(self.radius);
 
-        Some(HitRecord::new(ray, p, normal, root, self.material.clone()))
+        let uv = Self::uv(&normal);
+        let tangents = self.tangents(&normal);
+
+        Some(HitRecord::new(
+            ray,
+            p,
+            normal,
+            root,
+            uv,
+            tangents,
+            self.material.clone(),
+        ))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        if self
+            .hit(
+                &Ray::from(*origin, *direction),
+                &Interval::new(0.001, f64::INFINITY),
+            )
+            .is_none()
+        {
+            return 0.0;
+        }
+
+        let distance_squared = (self.center - *origin).length_squared();
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared)
+            .max(0.0)
+            .sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    // Samples directions uniformly within the cone subtended by the sphere
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let direction = self.center - *origin;
+        let distance_squared = direction.length_squared();
+        let uvw = Onb::new(&direction);
+
+        let mut rng = thread_rng();
+        let r1: f64 = rng.gen_range(0.0..1.0);
+        let r2: f64 = rng.gen_range(0.0..1.0);
+
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared)
+            .max(0.0)
+            .sqrt();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * r1;
+        let x = phi.cos() * (1.0 - z * z).sqrt();
+        let y = phi.sin() * (1.0 - z * z).sqrt();
+
+        uvw.local(&Vec3::from(x, y, z))
+    }
+
+    fn materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        materials.push(self.material.clone());
     }
 }
@@ -0,0 +1,110 @@
+//! Spatially varying material parameters
+
+use std::{path::Path, sync::Arc};
+
+use image::{ImageResult, Rgb, Rgb32FImage};
+
+use crate::vec3::{Color, Vec3};
+
+/// Spatially varying color, looked up by surface coordinates and hit point. Scalar parameters
+/// use the first channel.
+#[derive(Clone)]
+pub enum Texture {
+    /// Same color everywhere
+    Solid(Color),
+    /// Alternates between two textures in a 3D grid of cells `scale` wide
+    Checker {
+        /// Width of the cells in world units
+        scale: f64,
+        /// Texture of the cells whose coordinates add up to an even number
+        even: Box<Texture>,
+        /// Texture of the other cells
+        odd: Box<Texture>,
+    },
+    /// Linear image stretched over the surface coordinates, looked up at the nearest pixel
+    Image(Arc<Rgb32FImage>),
+}
+
+impl Texture {
+    /// Same value in every channel everywhere
+    pub fn scalar(value: f64) -> Texture {
+        Texture::Solid(Color::from(value, value, value))
+    }
+
+    /// Checker of `even` and `odd` cells `scale` wide
+    pub fn checker(scale: f64, even: impl Into<Texture>, odd: impl Into<Texture>) -> Texture {
+        Texture::Checker {
+            scale,
+            even: Box::new(even.into()),
+            odd: Box::new(odd.into()),
+        }
+    }
+
+    /// Loads an sRGB encoded image, converting it to linear color
+    pub fn image(path: impl AsRef<Path>) -> ImageResult<Texture> {
+        let mut image = image::open(path)?.into_rgb32f();
+        for pixel in image.pixels_mut() {
+            for channel in pixel.0.iter_mut() {
+                *channel = channel.powf(2.2);
+            }
+        }
+
+        Ok(Texture::Image(Arc::new(image)))
+    }
+
+    /// Loads an image holding data rather than color as it is, such as a normal map or heights
+    pub fn image_linear(path: impl AsRef<Path>) -> ImageResult<Texture> {
+        let image = image::open(path)?.into_rgb32f();
+        Ok(Texture::Image(Arc::new(image)))
+    }
+
+    /// Loads the alpha channel of an image as it is, as a scalar texture for opacity masks.
+    /// Images without one are fully opaque, grayscale masks are loaded with `image_linear`.
+    pub fn image_alpha(path: impl AsRef<Path>) -> ImageResult<Texture> {
+        let image = image::open(path)?.into_rgba32f();
+        let alpha = Rgb32FImage::from_fn(image.width(), image.height(), |x, y| {
+            let alpha = image.get_pixel(x, y)[3];
+            Rgb([alpha, alpha, alpha])
+        });
+
+        Ok(Texture::Image(Arc::new(alpha)))
+    }
+
+    /// Color at surface coordinates `u`, `v` and point `p`
+    pub fn value(&self, u: f64, v: f64, p: &Vec3) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Checker { scale, even, odd } => {
+                let cell =
+                    (p.x() / scale).floor() + (p.y() / scale).floor() + (p.z() / scale).floor();
+                if cell as i64 % 2 == 0 {
+                    even.value(u, v, p)
+                } else {
+                    odd.value(u, v, p)
+                }
+            }
+            Self::Image(image) => {
+                let u = u.clamp(0.0, 1.0);
+                // Image rows run top to bottom while v increases upwards
+                let v = 1.0 - v.clamp(0.0, 1.0);
+                let x = ((u * image.width() as f64) as u32).min(image.width() - 1);
+                let y = ((v * image.height() as f64) as u32).min(image.height() - 1);
+                let pixel = image.get_pixel(x, y);
+
+                Color::from(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64)
+            }
+        }
+    }
+}
+
+impl From<Color> for Texture {
+    fn from(color: Color) -> Self {
+        Texture::Solid(color)
+    }
+}
+
+impl From<f64> for Texture {
+    fn from(value: f64) -> Self {
+        Texture::scalar(value)
+    }
+}
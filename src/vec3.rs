@@ -1,20 +1,33 @@
+//! Vectors, points and colors in 3D
+
 use rand::{thread_rng, Rng};
+use std::f64::consts::PI;
 use std::ops::{Add, AddAssign, Deref, DerefMut, Div, Mul, MulAssign, Neg, Sub};
 
-#[derive(Debug, Copy, Clone)]
+/// Point or direction in 3D space
+#[derive(Debug, Copy, Clone, Default)]
 pub struct Vec3([f64; 3]);
 
+/// Linear RGB color
 pub type Color = Vec3;
 
 impl Color {
+    /// Color with all channels at zero
     pub fn black() -> Color {
         Color::from(0.0, 0.0, 0.0)
     }
 
+    /// Color with all channels at one
     pub fn white() -> Color {
         Color::from(1.0, 1.0, 1.0)
     }
 
+    /// Whether no channel is positive
+    pub fn is_black(&self) -> bool {
+        self[0] <= 0.0 && self[1] <= 0.0 && self[2] <= 0.0
+    }
+
+    /// Color with each channel uniformly random in [0, 1)
     pub fn random_color() -> Color {
         let mut rng = thread_rng();
         Vec3([
@@ -24,22 +37,27 @@ impl Color {
         ])
     }
 
+    /// Red channel scaled to 8 bits, which expects it to be in [0, 1]
     pub fn r(&self) -> u8 {
         (255.99 * self[0]) as u8
     }
 
+    /// Green channel scaled to 8 bits, which expects it to be in [0, 1]
     pub fn g(&self) -> u8 {
         (255.99 * self[1]) as u8
     }
 
+    /// Blue channel scaled to 8 bits, which expects it to be in [0, 1]
     pub fn b(&self) -> u8 {
         (255.99 * self[2]) as u8
     }
 
+    /// Multiplies every channel by `scale`
     pub fn scale(&mut self, scale: f64) {
         self.0 = [self.0[0] * scale, self.0[1] * scale, self.0[2] * scale]
     }
 
+    /// Converts linear color to gamma 2 for display
     pub fn to_gamma_space(&mut self) {
         self.0 = [self.0[0].sqrt(), self.0[1].sqrt(), self.0[2].sqrt()]
     }
@@ -58,55 +76,68 @@ impl DerefMut for Vec3 {
 }
 
 impl Vec3 {
+    /// Zero vector
     pub fn new() -> Vec3 {
         Vec3([0.0, 0.0, 0.0])
     }
+    /// Vector with the given components
     pub fn from(x: f64, y: f64, z: f64) -> Vec3 {
         Vec3([x, y, z])
     }
 
+    /// First component
     pub fn x(&self) -> f64 {
         self[0]
     }
 
+    /// Second component
     pub fn y(&self) -> f64 {
         self[1]
     }
 
+    /// Third component
     pub fn z(&self) -> f64 {
         self[2]
     }
 
+    /// Multiplies every component by `rhs`
     pub fn mul_assign(&mut self, rhs: f64) {
         self[0] *= rhs;
+        self[1] *= rhs;
         self[2] *= rhs;
-        self[3] *= rhs;
     }
 
+    /// Divides every component by `rhs`
     pub fn div_assign(&mut self, rhs: f64) {
         self.mul_assign(1.0 / rhs);
     }
 
+    /// Vector with every component multiplied by `rhs`
     pub fn mul(&self, rhs: f64) -> Vec3 {
         Vec3([self[0] * rhs, self[1] * rhs, self[2] * rhs])
     }
 
+    /// Vector with every component divided by `rhs`
     pub fn div(&self, rhs: f64) -> Vec3 {
         Vec3([self[0] / rhs, self[1] / rhs, self[2] / rhs])
     }
 
+    /// Euclidean length
     pub fn length(&self) -> f64 {
         self.length_squared().sqrt()
     }
 
+    /// Square of the length, which saves a square root when comparing lengths
     pub fn length_squared(&self) -> f64 {
         self[0] * self[0] + self[1] * self[1] + self[2] * self[2]
     }
 
+    /// Dot product
     pub fn dot(&self, rhs: &Vec3) -> f64 {
         self[0] * rhs[0] + self[1] * rhs[1] + self[2] * rhs[2]
     }
 
+    /// Cross product, following the right hand rule
     pub fn cross(&self, rhs: &Vec3) -> Vec3 {
         Vec3([
             self[1] * rhs[2] - self[2] * rhs[1],
@@ -115,14 +146,17 @@ impl Vec3 {
         ])
     }
 
+    /// Vector of length one in the same direction
     pub fn unit(&self) -> Vec3 {
         self.div(self.length())
     }
 
+    /// Vector with each component uniformly random in [0, 1)
     pub fn random() -> Vec3 {
         Self::random_constrained(0.0, 1.0)
     }
 
+    /// Vector with each component uniformly random in [`min`, `max`)
     pub fn random_constrained(min: f64, max: f64) -> Vec3 {
         let mut rng = thread_rng();
         let x = rng.gen_range(min..max);
@@ -131,6 +165,12 @@ impl Vec3 {
         Vec3([x, y, z])
     }
 
+    /// Largest of the three components
+    pub fn max_component(&self) -> f64 {
+        self[0].max(self[1]).max(self[2])
+    }
+
+    /// Whether every component is close to zero, as in degenerate scattering directions
     pub fn near_zero(&self) -> bool {
         static S: f64 = 1e-8;
 
@@ -203,14 +243,33 @@ fn random_in_unit_sphere() -> Vec3 {
     }
 }
 
+/// Uniformly random direction
 pub fn random_unit_vector() -> Vec3 {
     random_in_unit_sphere().unit()
 }
 
+/// Direction in the hemisphere around +z, distributed proportionally to its cosine with z
+pub fn random_cosine_direction() -> Vec3 {
+    let mut rng = thread_rng();
+    let r1: f64 = rng.gen_range(0.0..1.0);
+    let r2: f64 = rng.gen_range(0.0..1.0);
+    let phi = 2.0 * PI * r1;
+
+    Vec3::from(
+        phi.cos() * r2.sqrt(),
+        phi.sin() * r2.sqrt(),
+        (1.0 - r2).sqrt(),
+    )
+}
+
+/// Mirror reflection of `v` about the plane with normal `n`
 pub fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {
-    v - &n.mul(v.dot(&n) * 2.0)
+    v - &n.mul(v.dot(n) * 2.0)
 }
 
+/// Refraction of the unit direction `uv` through a surface with normal `n` facing against it,
+/// given the ratio of the indices of refraction on either side. Assumes there's no total internal
+/// reflection.
 pub fn refract(uv: &Vec3, n: &Vec3, etai_over_etat: f64) -> Vec3 {
     let cos_theta = -uv.dot(n).min(1.0);
     let r_out_perp: Vec3 = n.mul(cos_theta) + *uv;
@@ -220,6 +279,7 @@ pub fn refract(uv: &Vec3, n: &Vec3, etai_over_etat: f64) -> Vec3 {
     r_out_perp + r_out_parallel
 }
 
+/// Uniformly random point in the unit disk in the xy plane
 pub fn random_in_unit_disk() -> Vec3 {
     let mut rng = thread_rng();
     loop {
@@ -229,3 +289,27 @@ pub fn random_in_unit_disk() -> Vec3 {
         }
     }
 }
+
+/// Random point in the regular polygon with `sides` corners on the unit circle, the first at
+/// `rotation` radians
+pub fn random_in_unit_polygon(sides: u32, rotation: f64) -> Vec3 {
+    if sides < 3 {
+        return random_in_unit_disk();
+    }
+
+    // The polygon is a fan of equal triangles around the origin, pick one and a point in it
+    let mut rng = thread_rng();
+    let side = rng.gen_range(0..sides) as f64;
+    let step = 2.0 * PI / sides as f64;
+    let a = rotation + side * step;
+    let (a, b) = (
+        &Vec3::from(a.cos(), a.sin(), 0.0),
+        &Vec3::from((a + step).cos(), (a + step).sin(), 0.0),
+    );
+
+    let (mut s, mut t) = (rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0));
+    if s + t > 1.0 {
+        (s, t) = (1.0 - s, 1.0 - t);
+    }
+    a.mul(s) + b.mul(t)
+}